@@ -0,0 +1,27 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+// The wrappers load the Python library at runtime: only the unit tests of this crate, which embed
+// Python, are linked against it. The link arguments of a build script are not propagated to the
+// dependent crates, and the `extension-module` feature of `zenoh-flow-python` and
+// `zenoh-flow-python-nodes` disables the linking done by PyO3 when the whole workspace is built.
+fn main() {
+    let config = pyo3_build_config::get();
+    if let Some(lib_dir) = &config.lib_dir {
+        println!("cargo:rustc-link-arg=-L{lib_dir}");
+    }
+    if let Some(lib_name) = &config.lib_name {
+        println!("cargo:rustc-link-arg=-l{lib_name}");
+    }
+}
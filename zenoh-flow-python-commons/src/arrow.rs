@@ -0,0 +1,171 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+//! Exchange of Apache Arrow `RecordBatch`es between nodes.
+//!
+//! On the wire, a message holds exactly one `RecordBatch` in the Arrow IPC *streaming* format:
+//! the schema message, the record batch message and the end-of-stream marker. Rust nodes can
+//! therefore read the data sent by Python nodes with `arrow_ipc::reader::StreamReader`, and send
+//! batches to Python nodes with `arrow_ipc::writer::StreamWriter`.
+//!
+//! When both nodes run in the same process, the `pyarrow.RecordBatch` is handed over as is and
+//! never serialized.
+
+use crate::buffer::{BufferInner, PyPayload};
+use pyo3::exceptions::{PyImportError, PyStopIteration, PyTypeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyType;
+use std::sync::Arc;
+use zenoh_flow::prelude::{zferror, ErrorKind, SendSyncAny};
+use zenoh_flow::types::Payload;
+
+fn import_pyarrow(py: Python) -> PyResult<&PyModule> {
+    py.import("pyarrow").map_err(|e| {
+        PyImportError::new_err(format!(
+            "Arrow ports require the `pyarrow` package to be installed: {e}"
+        ))
+    })
+}
+
+/// A `pyarrow.RecordBatch` sent to a node running in the same process.
+struct RecordBatchPayload(PyObject);
+
+/// Writes `batch` in the Arrow IPC streaming format.
+fn write_ipc_stream(batch: &PyAny) -> PyResult<Vec<u8>> {
+    let pyarrow = import_pyarrow(batch.py())?;
+    let sink = pyarrow.call_method0("BufferOutputStream")?;
+    let writer = pyarrow
+        .getattr("ipc")?
+        .call_method1("new_stream", (sink, batch.getattr("schema")?))?;
+    writer.call_method1("write_batch", (batch,))?;
+    writer.call_method0("close")?;
+
+    let buffer = PyPayload::new(sink.call_method0("getvalue")?)?;
+    Ok(buffer.as_ref().to_vec())
+}
+
+/// Reads the single `RecordBatch` contained in the Arrow IPC stream `bytes`.
+fn read_ipc_stream(py: Python, bytes: Arc<Vec<u8>>) -> PyResult<PyObject> {
+    let pyarrow = import_pyarrow(py)?;
+    let buffer = pyarrow.call_method1(
+        "py_buffer",
+        (BufferInner::Rust(bytes).into_memoryview(py)?,),
+    )?;
+    let reader = pyarrow
+        .getattr("ipc")?
+        .call_method1("open_stream", (buffer,))?;
+
+    // Reading batch by batch: a zero-row batch would vanish from a `read_all` table.
+    let stop_iteration = py.get_type::<PyStopIteration>();
+    let batch = reader.call_method0("read_next_batch").map_err(|e| {
+        if e.is_instance(py, stop_iteration) {
+            PyValueError::new_err("Expected a single RecordBatch per message, received none")
+        } else {
+            e
+        }
+    })?;
+    match reader.call_method0("read_next_batch") {
+        Err(e) if e.is_instance(py, stop_iteration) => Ok(batch.into()),
+        Err(e) => Err(e),
+        Ok(_) => Err(PyValueError::new_err(
+            "Expected a single RecordBatch per message, received more",
+        )),
+    }
+}
+
+/// Wraps the `pyarrow.RecordBatch` in a [`Payload`](`Payload`).
+///
+/// The batch is only serialized if it has to, i.e. when it is sent to a node that is not a
+/// Python node running in the same process.
+pub(crate) fn encode(batch: &PyAny) -> PyResult<Payload> {
+    let pyarrow = import_pyarrow(batch.py())?;
+    if !batch.is_instance(pyarrow.getattr("RecordBatch")?.downcast::<PyType>()?)? {
+        return Err(PyTypeError::new_err(format!(
+            "Expected a pyarrow.RecordBatch, got {}",
+            batch.get_type().name()?
+        )));
+    }
+
+    Ok(Payload::Typed((
+        Arc::new(RecordBatchPayload(batch.into())) as Arc<dyn SendSyncAny>,
+        Arc::new(|buffer: &mut Vec<u8>, payload: Arc<dyn SendSyncAny>| {
+            let payload = (*payload)
+                .as_any()
+                .downcast_ref::<RecordBatchPayload>()
+                .ok_or_else(|| {
+                    zferror!(
+                        ErrorKind::SerializationError,
+                        "Payload is not a pyarrow.RecordBatch"
+                    )
+                })?;
+            let bytes = Python::with_gil(|py| write_ipc_stream(payload.0.as_ref(py)))
+                .map_err(|e| zferror!(ErrorKind::SerializationError, "{}", e))?;
+            buffer.extend_from_slice(&bytes);
+            Ok(())
+        }),
+    )))
+}
+
+/// Returns the `pyarrow.RecordBatch` contained in the [`Payload`](`Payload`).
+pub(crate) fn decode(py: Python, payload: &Payload) -> PyResult<PyObject> {
+    if let Payload::Typed((data, _)) = payload {
+        if let Some(batch) = (**data).as_any().downcast_ref::<RecordBatchPayload>() {
+            return Ok(batch.0.clone_ref(py));
+        }
+    }
+
+    let bytes = payload
+        .try_as_bytes()
+        .map_err(|e| PyValueError::new_err(format!("try_as_bytes field: {e}")))?;
+    read_ipc_stream(py, bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[ignore = "requires pyarrow, run with `cargo test -- --ignored`"]
+    fn zero_row_batch_round_trip() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let pyarrow = py.import("pyarrow").unwrap();
+            let locals = pyo3::types::PyDict::new(py);
+            locals.set_item("pyarrow", pyarrow).unwrap();
+            let batch = py
+                .eval(
+                    "pyarrow.record_batch({'x': pyarrow.array([], type=pyarrow.int64())})",
+                    None,
+                    Some(locals),
+                )
+                .unwrap();
+
+            let bytes = write_ipc_stream(batch).unwrap();
+            let decoded = read_ipc_stream(py, Arc::new(bytes)).unwrap();
+            assert_eq!(
+                decoded
+                    .getattr(py, "num_rows")
+                    .unwrap()
+                    .extract::<usize>(py)
+                    .unwrap(),
+                0
+            );
+            assert!(decoded
+                .call_method1(py, "equals", (batch,))
+                .unwrap()
+                .extract::<bool>(py)
+                .unwrap());
+        });
+    }
+}
@@ -0,0 +1,302 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+use pyo3::once_cell::GILOnceCell;
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyType};
+use pyo3::{ffi, AsPyPointer};
+use std::mem::{ManuallyDrop, MaybeUninit};
+use std::os::raw::{c_int, c_void};
+use std::sync::Arc;
+use zenoh_flow::prelude::{zferror, ErrorKind, SendSyncAny};
+use zenoh_flow::types::Payload;
+
+/// The subset of the buffer protocol we rely on.
+///
+/// pyo3 only exposes it when not targeting the limited API (or when targeting Python >= 3.11).
+/// These symbols and the layout of `Py_buffer` are nonetheless identical in every CPython 3
+/// release, and part of the stable ABI since 3.11.
+#[allow(non_camel_case_types, non_snake_case, non_upper_case_globals)]
+mod ffi_buffer {
+    use pyo3::ffi::{PyObject, Py_ssize_t};
+    use std::os::raw::{c_char, c_int, c_void};
+
+    #[repr(C)]
+    pub struct Py_buffer {
+        pub buf: *mut c_void,
+        pub obj: *mut PyObject,
+        pub len: Py_ssize_t,
+        pub itemsize: Py_ssize_t,
+        pub readonly: c_int,
+        pub ndim: c_int,
+        pub format: *mut c_char,
+        pub shape: *mut Py_ssize_t,
+        pub strides: *mut Py_ssize_t,
+        pub suboffsets: *mut Py_ssize_t,
+        pub internal: *mut c_void,
+    }
+
+    pub const PyBUF_SIMPLE: c_int = 0;
+
+    extern "C" {
+        pub fn PyObject_GetBuffer(obj: *mut PyObject, view: *mut Py_buffer, flags: c_int) -> c_int;
+        pub fn PyBuffer_Release(view: *mut Py_buffer);
+        pub fn PyBuffer_FillInfo(
+            view: *mut Py_buffer,
+            o: *mut PyObject,
+            buf: *mut c_void,
+            len: Py_ssize_t,
+            readonly: c_int,
+            flags: c_int,
+        ) -> c_int;
+    }
+}
+
+use ffi_buffer::Py_buffer;
+
+/// A buffer exported by a Python object (`bytes`, `bytearray`, `memoryview`, NumPy array, …).
+///
+/// The export is held for as long as this structure lives: the exporting object is kept alive
+/// and, for resizable objects such as `bytearray`, cannot be resized in the meantime. The content
+/// of a mutable exporter should nonetheless not be modified once it has been sent.
+pub struct PyPayload {
+    view: Box<Py_buffer>,
+}
+
+// SAFETY: the memory pointed to by the view is kept alive by the reference to the exporter the
+// view holds, and the view is only released with the GIL held (see `Drop`).
+unsafe impl Send for PyPayload {}
+unsafe impl Sync for PyPayload {}
+
+impl PyPayload {
+    /// Acquires a read-only buffer over the content of `obj`.
+    ///
+    /// A `BufferError` is raised if `obj` does not implement the buffer protocol or if its content
+    /// is not contiguous.
+    pub fn new(obj: &PyAny) -> PyResult<Self> {
+        let mut view = Box::new(MaybeUninit::<Py_buffer>::uninit());
+        unsafe {
+            if ffi_buffer::PyObject_GetBuffer(
+                obj.as_ptr(),
+                view.as_mut_ptr(),
+                ffi_buffer::PyBUF_SIMPLE,
+            ) == -1
+            {
+                return Err(PyErr::fetch(obj.py()));
+            }
+
+            Ok(Self {
+                view: Box::from_raw(Box::into_raw(view) as *mut Py_buffer),
+            })
+        }
+    }
+}
+
+impl AsRef<[u8]> for PyPayload {
+    fn as_ref(&self) -> &[u8] {
+        if self.view.len == 0 {
+            return &[];
+        }
+
+        unsafe { std::slice::from_raw_parts(self.view.buf as *const u8, self.view.len as usize) }
+    }
+}
+
+impl Drop for PyPayload {
+    fn drop(&mut self) {
+        Python::with_gil(|_| unsafe { ffi_buffer::PyBuffer_Release(&mut *self.view) })
+    }
+}
+
+impl From<PyPayload> for Payload {
+    /// The Python buffer is only copied if the payload has to be serialized, i.e. when it is sent
+    /// to a node that is not running in the same process.
+    fn from(payload: PyPayload) -> Self {
+        Payload::Typed((
+            Arc::new(payload) as Arc<dyn SendSyncAny>,
+            Arc::new(|buffer: &mut Vec<u8>, data: Arc<dyn SendSyncAny>| {
+                let payload = (*data)
+                    .as_any()
+                    .downcast_ref::<PyPayload>()
+                    .ok_or_else(|| {
+                        zferror!(
+                            ErrorKind::SerializationError,
+                            "Payload is not a Python buffer"
+                        )
+                    })?;
+                buffer.extend_from_slice(payload.as_ref());
+                Ok(())
+            }),
+        ))
+    }
+}
+
+/// The memory backing the `memoryview` handed to Python nodes.
+#[derive(Clone)]
+pub(crate) enum BufferInner {
+    Rust(Arc<Vec<u8>>),
+    Python(Arc<dyn SendSyncAny>),
+}
+
+impl BufferInner {
+    /// Returns the memory backing the provided [`Payload`](`Payload`), without copy when the
+    /// payload holds bytes or a Python buffer.
+    pub(crate) fn try_from_payload(payload: &Payload) -> zenoh_flow::Result<Self> {
+        if let Payload::Typed((data, _)) = payload {
+            if (**data).as_any().is::<PyPayload>() {
+                return Ok(Self::Python(data.clone()));
+            }
+        }
+
+        Ok(Self::Rust(payload.try_as_bytes()?))
+    }
+
+    fn as_slice(&self) -> &[u8] {
+        match self {
+            Self::Rust(bytes) => bytes.as_slice(),
+            Self::Python(data) => (**data)
+                .as_any()
+                .downcast_ref::<PyPayload>()
+                .map_or(&[], |payload| payload.as_ref()),
+        }
+    }
+
+    /// Returns a read-only `memoryview` over this memory.
+    ///
+    /// The `memoryview` keeps the memory alive. Python < 3.9 does not allow defining the buffer
+    /// protocol through the limited API, the memory is copied in that case.
+    pub(crate) fn into_memoryview(self, py: Python) -> PyResult<PyObject> {
+        let exporter = match buffer_type(py) {
+            Some(buffer_type) => unsafe { BufferObject::alloc(py, buffer_type, self)? },
+            None => PyBytes::new(py, self.as_slice()).to_object(py),
+        };
+
+        unsafe {
+            PyObject::from_owned_ptr_or_err(py, ffi::PyMemoryView_FromObject(exporter.as_ptr()))
+        }
+    }
+}
+
+/// A Python object exporting a [`BufferInner`](`BufferInner`) through the buffer protocol.
+#[repr(C)]
+struct BufferObject {
+    ob_base: ffi::PyObject,
+    inner: ManuallyDrop<BufferInner>,
+}
+
+impl BufferObject {
+    unsafe fn alloc(
+        py: Python,
+        buffer_type: &Py<PyType>,
+        inner: BufferInner,
+    ) -> PyResult<PyObject> {
+        let obj = ffi::PyType_GenericAlloc(buffer_type.as_ptr() as *mut ffi::PyTypeObject, 0);
+        if obj.is_null() {
+            return Err(PyErr::fetch(py));
+        }
+
+        std::ptr::write(
+            &mut (*(obj as *mut BufferObject)).inner,
+            ManuallyDrop::new(inner),
+        );
+        Ok(PyObject::from_owned_ptr(py, obj))
+    }
+}
+
+unsafe extern "C" fn buffer_object_new(
+    _subtype: *mut ffi::PyTypeObject,
+    _args: *mut ffi::PyObject,
+    _kwds: *mut ffi::PyObject,
+) -> *mut ffi::PyObject {
+    ffi::PyErr_SetString(
+        ffi::PyExc_TypeError,
+        "RawBuffer cannot be instantiated from Python\0".as_ptr() as *const _,
+    );
+    std::ptr::null_mut()
+}
+
+unsafe extern "C" fn buffer_object_getbuffer(
+    obj: *mut ffi::PyObject,
+    view: *mut Py_buffer,
+    flags: c_int,
+) -> c_int {
+    let bytes = (*(obj as *mut BufferObject)).inner.as_slice();
+    ffi_buffer::PyBuffer_FillInfo(
+        view,
+        obj,
+        bytes.as_ptr() as *mut c_void,
+        bytes.len() as ffi::Py_ssize_t,
+        1,
+        flags,
+    )
+}
+
+unsafe extern "C" fn buffer_object_dealloc(obj: *mut ffi::PyObject) {
+    ManuallyDrop::drop(&mut (*(obj as *mut BufferObject)).inner);
+
+    let obj_type = ffi::Py_TYPE(obj);
+    let free: ffi::freefunc = std::mem::transmute(ffi::PyType_GetSlot(obj_type, ffi::Py_tp_free));
+    free(obj as *mut c_void);
+    ffi::Py_DECREF(obj_type as *mut ffi::PyObject);
+}
+
+/// Returns the type of [`BufferObject`](`BufferObject`), created on first use, or `None` if the
+/// interpreter does not support defining the buffer protocol through the limited API.
+fn buffer_type(py: Python) -> Option<&Py<PyType>> {
+    static BUFFER_TYPE: GILOnceCell<Option<Py<PyType>>> = GILOnceCell::new();
+
+    BUFFER_TYPE
+        .get_or_init(py, || {
+            if py.version_info() < (3, 9) {
+                return None;
+            }
+
+            let mut slots = [
+                ffi::PyType_Slot {
+                    slot: ffi::Py_tp_new,
+                    pfunc: buffer_object_new as *mut c_void,
+                },
+                ffi::PyType_Slot {
+                    slot: ffi::Py_tp_dealloc,
+                    pfunc: buffer_object_dealloc as *mut c_void,
+                },
+                ffi::PyType_Slot {
+                    slot: ffi::Py_bf_getbuffer,
+                    pfunc: buffer_object_getbuffer as *mut c_void,
+                },
+                ffi::PyType_Slot {
+                    slot: 0,
+                    pfunc: std::ptr::null_mut(),
+                },
+            ];
+            let mut spec = ffi::PyType_Spec {
+                name: "zenoh_flow.RawBuffer\0".as_ptr() as *const _,
+                basicsize: std::mem::size_of::<BufferObject>() as c_int,
+                itemsize: 0,
+                flags: ffi::Py_TPFLAGS_DEFAULT as _,
+                slots: slots.as_mut_ptr(),
+            };
+
+            unsafe {
+                match Py::<PyType>::from_owned_ptr_or_err(py, ffi::PyType_FromSpec(&mut spec)) {
+                    Ok(buffer_type) => Some(buffer_type),
+                    Err(e) => {
+                        log::warn!("Unable to create the Python buffer type, data is copied: {e}");
+                        None
+                    }
+                }
+            }
+        })
+        .as_ref()
+}
@@ -0,0 +1,357 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+//! The codecs built in Zenoh Flow: JSON, MessagePack, CBOR and pickle.
+//!
+//! JSON, MessagePack and CBOR support the Python types that have a counterpart in these formats:
+//! `None`, `bool`, `int` (within the 64 bits range), `float`, `str`, `bytes`, `list`, `tuple` and
+//! `dict`. Tuples are decoded as lists. JSON has no binary type and no NaN nor infinities: encoding
+//! `bytes`, `NaN` or `Infinity` in JSON raises a `ValueError`.
+
+use crate::buffer::{BufferInner, PyPayload};
+use pyo3::exceptions::{PyTypeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{
+    PyBool, PyByteArray, PyBytes, PyDict, PyFloat, PyList, PyLong, PyString, PyTuple,
+};
+use serde::de::{self, DeserializeSeed, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::ser::{self, Serialize, SerializeMap, SerializeSeq, Serializer};
+use std::fmt;
+use std::sync::Arc;
+
+/// A codec implemented by Zenoh Flow.
+///
+/// `Codec("json")` encodes Python objects with `encode` and decodes the received data with
+/// `decode`. The supported formats are: `"json"`, `"msgpack"`, `"cbor"` and `"pickle"`.
+///
+/// Decoding with `"pickle"` can run arbitrary code sent by an upstream node: it should only be used
+/// within a trusted data flow.
+#[pyclass]
+#[derive(Clone, Copy, Debug)]
+pub struct Codec {
+    format: Format,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Format {
+    Json,
+    MsgPack,
+    Cbor,
+    Pickle,
+}
+
+#[pymethods]
+impl Codec {
+    #[new]
+    pub fn new(name: &str) -> PyResult<Self> {
+        let format = match name {
+            "json" => Format::Json,
+            "msgpack" => Format::MsgPack,
+            "cbor" => Format::Cbor,
+            "pickle" => Format::Pickle,
+            _ => {
+                return Err(PyValueError::new_err(format!(
+                    "Unknown codec {name}, expected one of: json, msgpack, cbor, pickle"
+                )))
+            }
+        };
+
+        Ok(Self { format })
+    }
+
+    /// Encodes `obj`, returning a read-only `memoryview` over the encoded bytes.
+    pub fn encode(&self, py: Python, obj: &PyAny) -> PyResult<PyObject> {
+        let bytes = match self.format {
+            Format::Json => {
+                check_json(obj)?;
+                serde_json::to_vec(&PySerialize(obj)).map_err(encode_error)?
+            }
+            Format::MsgPack => rmp_serde::to_vec(&PySerialize(obj)).map_err(encode_error)?,
+            Format::Cbor => {
+                let mut bytes = Vec::new();
+                ciborium::ser::into_writer(&PySerialize(obj), &mut bytes).map_err(encode_error)?;
+                bytes
+            }
+            Format::Pickle => {
+                return py
+                    .import("pickle")?
+                    .call_method1("dumps", (obj,))
+                    .map(|bytes| bytes.into())
+            }
+        };
+
+        BufferInner::Rust(Arc::new(bytes)).into_memoryview(py)
+    }
+
+    /// Decodes `data`, any object implementing the buffer protocol.
+    pub fn decode(&self, py: Python, data: &PyAny) -> PyResult<PyObject> {
+        if let Format::Pickle = self.format {
+            return py
+                .import("pickle")?
+                .call_method1("loads", (data,))
+                .map(|obj| obj.into());
+        }
+
+        let payload = PyPayload::new(data)?;
+        let bytes = payload.as_ref();
+        let decoded = match self.format {
+            Format::Json => serde_json::from_slice::<PyDecoded>(bytes).map_err(decode_error)?,
+            Format::MsgPack => rmp_serde::from_slice::<PyDecoded>(bytes).map_err(decode_error)?,
+            Format::Cbor => {
+                ciborium::de::from_reader::<PyDecoded, _>(bytes).map_err(decode_error)?
+            }
+            Format::Pickle => unreachable!("pickle is decoded by Python"),
+        };
+
+        Ok(decoded.0)
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Codec({:?})", self.format)
+    }
+}
+
+fn encode_error(e: impl fmt::Display) -> PyErr {
+    PyTypeError::new_err(format!("Unable to encode: {e}"))
+}
+
+fn decode_error(e: impl fmt::Display) -> PyErr {
+    PyValueError::new_err(format!("Unable to decode: {e}"))
+}
+
+/// Checks that `obj` can be encoded in JSON as is: `serde_json` would otherwise encode `bytes` as
+/// lists of integers, and `NaN` and infinities as `null`.
+fn check_json(obj: &PyAny) -> PyResult<()> {
+    if obj.is_instance_of::<PyBytes>()? || obj.is_instance_of::<PyByteArray>()? {
+        Err(PyValueError::new_err(
+            "Unable to encode: JSON has no binary type, use msgpack or cbor to encode bytes",
+        ))
+    } else if let Ok(f) = obj.downcast::<PyFloat>() {
+        if f.value().is_finite() {
+            Ok(())
+        } else {
+            Err(PyValueError::new_err(format!(
+                "Unable to encode: JSON has no NaN nor infinities, found {}",
+                f.value()
+            )))
+        }
+    } else if let Ok(l) = obj.downcast::<PyList>() {
+        l.iter().try_for_each(check_json)
+    } else if let Ok(t) = obj.downcast::<PyTuple>() {
+        t.iter().try_for_each(check_json)
+    } else if let Ok(d) = obj.downcast::<PyDict>() {
+        d.iter()
+            .try_for_each(|(key, value)| check_json(key).and_then(|_| check_json(value)))
+    } else {
+        Ok(())
+    }
+}
+
+/// Serializes a Python object with serde.
+struct PySerialize<'a>(&'a PyAny);
+
+impl Serialize for PySerialize<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let obj = self.0;
+
+        if obj.is_none() {
+            serializer.serialize_none()
+        } else if let Ok(b) = obj.downcast::<PyBool>() {
+            serializer.serialize_bool(b.is_true())
+        } else if obj.is_instance_of::<PyLong>().map_err(ser::Error::custom)? {
+            match obj.extract::<i64>() {
+                Ok(i) => serializer.serialize_i64(i),
+                Err(_) => serializer.serialize_u64(obj.extract().map_err(ser::Error::custom)?),
+            }
+        } else if let Ok(f) = obj.downcast::<PyFloat>() {
+            serializer.serialize_f64(f.value())
+        } else if let Ok(s) = obj.downcast::<PyString>() {
+            serializer.serialize_str(s.to_str().map_err(ser::Error::custom)?)
+        } else if let Ok(b) = obj.downcast::<PyBytes>() {
+            serializer.serialize_bytes(b.as_bytes())
+        } else if let Ok(b) = obj.downcast::<PyByteArray>() {
+            serializer.serialize_bytes(&b.to_vec())
+        } else if let Ok(l) = obj.downcast::<PyList>() {
+            let mut seq = serializer.serialize_seq(Some(l.len()))?;
+            for item in l.iter() {
+                seq.serialize_element(&PySerialize(item))?;
+            }
+            seq.end()
+        } else if let Ok(t) = obj.downcast::<PyTuple>() {
+            let mut seq = serializer.serialize_seq(Some(t.len()))?;
+            for item in t.iter() {
+                seq.serialize_element(&PySerialize(item))?;
+            }
+            seq.end()
+        } else if let Ok(d) = obj.downcast::<PyDict>() {
+            let mut map = serializer.serialize_map(Some(d.len()))?;
+            for (key, value) in d.iter() {
+                map.serialize_entry(&PySerialize(key), &PySerialize(value))?;
+            }
+            map.end()
+        } else {
+            Err(ser::Error::custom(format!(
+                "unsupported type {}",
+                obj.get_type().name().unwrap_or("<unknown>")
+            )))
+        }
+    }
+}
+
+/// A Python object deserialized with serde.
+struct PyDecoded(PyObject);
+
+impl<'de> de::Deserialize<'de> for PyDecoded {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Python::with_gil(|py| PyVisitor { py }.deserialize(deserializer).map(PyDecoded))
+    }
+}
+
+#[derive(Clone, Copy)]
+struct PyVisitor<'py> {
+    py: Python<'py>,
+}
+
+impl<'de, 'py> DeserializeSeed<'de> for PyVisitor<'py> {
+    type Value = PyObject;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<PyObject, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de, 'py> Visitor<'de> for PyVisitor<'py> {
+    type Value = PyObject;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a value")
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<PyObject, E> {
+        Ok(v.to_object(self.py))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<PyObject, E> {
+        Ok(v.to_object(self.py))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<PyObject, E> {
+        Ok(v.to_object(self.py))
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<PyObject, E> {
+        Ok(v.to_object(self.py))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<PyObject, E> {
+        Ok(v.to_object(self.py))
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<PyObject, E> {
+        Ok(PyBytes::new(self.py, v).into())
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<PyObject, E> {
+        Ok(self.py.None())
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<PyObject, E> {
+        Ok(self.py.None())
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<PyObject, D::Error> {
+        self.deserialize(deserializer)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<PyObject, A::Error> {
+        let list = PyList::empty(self.py);
+        while let Some(item) = seq.next_element_seed(self)? {
+            list.append(item).map_err(de::Error::custom)?;
+        }
+        Ok(list.into())
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<PyObject, A::Error> {
+        let dict = PyDict::new(self.py);
+        while let Some((key, value)) = map.next_entry_seed(self, self)? {
+            dict.set_item(key, value).map_err(de::Error::custom)?;
+        }
+        Ok(dict.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(py: Python, codec: &str, obj: &PyAny) -> PyObject {
+        let codec = Codec::new(codec).unwrap();
+        let encoded = codec.encode(py, obj).unwrap();
+        codec.decode(py, encoded.as_ref(py)).unwrap()
+    }
+
+    #[test]
+    fn round_trips() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let common = py
+                .eval(
+                    "{'none': None, 'bool': True, 'int': -42, 'big': 2 ** 64 - 1, 'float': 2.5, \
+                      'str': 'zenoh', 'list': [1, [2, {'three': 3}]], 'empty': {}}",
+                    None,
+                    None,
+                )
+                .unwrap();
+            for codec in ["json", "msgpack", "cbor", "pickle"] {
+                let decoded = round_trip(py, codec, common);
+                assert!(decoded.as_ref(py).eq(common).unwrap(), "{}", codec);
+            }
+
+            let binary = py.eval("{'bytes': b'\\x00\\xff'}", None, None).unwrap();
+            for codec in ["msgpack", "cbor", "pickle"] {
+                let decoded = round_trip(py, codec, binary);
+                assert!(decoded.as_ref(py).eq(binary).unwrap(), "{}", codec);
+            }
+
+            // Tuples are decoded as lists.
+            let tuple = py.eval("(1, 'two')", None, None).unwrap();
+            let list = py.eval("[1, 'two']", None, None).unwrap();
+            for codec in ["json", "msgpack", "cbor"] {
+                let decoded = round_trip(py, codec, tuple);
+                assert!(decoded.as_ref(py).eq(list).unwrap(), "{}", codec);
+            }
+        });
+    }
+
+    #[test]
+    fn json_rejects_bytes_and_non_finite_floats() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let json = Codec::new("json").unwrap();
+            for obj in [
+                "b'zenoh'",
+                "bytearray(b'zenoh')",
+                "[{'nested': b'zenoh'}]",
+                "float('nan')",
+                "{'inf': float('inf')}",
+                "(float('-inf'),)",
+            ] {
+                let err = json
+                    .encode(py, py.eval(obj, None, None).unwrap())
+                    .unwrap_err();
+                assert!(err.is_instance_of::<PyValueError>(py), "{}: {}", obj, err);
+            }
+        });
+    }
+}
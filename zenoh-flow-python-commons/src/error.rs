@@ -0,0 +1,262 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+//! The exceptions raised by Python nodes.
+//!
+//! A Python exception is converted into a `ZFError` whose kind depends on the class of the
+//! exception, see [`PythonError::kind`], and whose description is a single line. The exception
+//! itself, a [`PythonError`], is the source of the `ZFError`: it keeps the class, the message and
+//! the traceback of the exception, and is rendered like Python renders it.
+//!
+//! Zenoh Flow does not give their id to the nodes: the wrappers read it from the optional
+//! `node-id` key of the node configuration, which is removed from the configuration given to the
+//! Python node.
+
+use pyo3::exceptions::{
+    PyConnectionError, PyFileNotFoundError, PyImportError, PyNotImplementedError, PyOSError,
+    PyTypeError, PyValueError,
+};
+use pyo3::prelude::*;
+use std::fmt;
+use zenoh_flow::prelude::{zferror, Configuration, Error, ErrorKind, Result};
+
+const NODE_ID: &str = "node-id";
+
+/// Takes the `node-id` key out of the `configuration`.
+///
+/// # Errors
+///
+/// This function fails if the `node-id` is not a string.
+pub(crate) fn take_node_id(configuration: &mut Configuration) -> Result<Option<String>> {
+    match configuration.as_object_mut() {
+        Some(configuration) => crate::loader::take_string(configuration, NODE_ID),
+        None => Ok(None),
+    }
+}
+
+/// A frame of the traceback of a Python exception.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PythonFrame {
+    pub file: String,
+    pub line: usize,
+    pub function: String,
+}
+
+/// An exception raised by a Python node.
+#[derive(Clone, PartialEq, Eq)]
+pub struct PythonError {
+    /// The class of the exception, qualified by its module unless it is a builtin one.
+    pub exception: String,
+    pub message: String,
+    /// The frames of the traceback, the most recent call last.
+    pub frames: Vec<PythonFrame>,
+    /// The id of the node that raised the exception, if known.
+    pub node_id: Option<String>,
+    kind: ErrorKind,
+}
+
+impl PythonError {
+    pub fn new(py: Python, err: &PyErr) -> Self {
+        let exception = err.get_type(py);
+        let name = exception.name().unwrap_or("<unknown>").to_string();
+        let exception = match exception
+            .getattr("__module__")
+            .and_then(|module| module.extract::<String>())
+        {
+            Ok(module) if module != "builtins" => format!("{module}.{name}"),
+            _ => name,
+        };
+
+        let message = err
+            .value(py)
+            .str()
+            .map_or_else(|_| "<unprintable exception>".to_string(), |s| s.to_string());
+
+        let frames = err
+            .traceback(py)
+            .map(|traceback| extract_frames(py, traceback).unwrap_or_default())
+            .unwrap_or_default();
+
+        let kind = if err.is_instance_of::<PyNotImplementedError>(py) {
+            ErrorKind::Unimplemented
+        } else if err.is_instance_of::<PyImportError>(py)
+            || err.is_instance_of::<PyFileNotFoundError>(py)
+        {
+            ErrorKind::NotFound
+        } else if err.is_instance_of::<PyConnectionError>(py) {
+            ErrorKind::Disconnected
+        } else if err.is_instance_of::<PyOSError>(py) {
+            ErrorKind::IOError
+        } else if err.is_instance_of::<PyValueError>(py) || err.is_instance_of::<PyTypeError>(py) {
+            ErrorKind::InvalidData
+        } else {
+            ErrorKind::GenericError
+        };
+
+        Self {
+            exception,
+            message,
+            frames,
+            node_id: None,
+            kind,
+        }
+    }
+
+    /// Sets the id of the node that raised the exception.
+    pub fn with_node_id(mut self, node_id: Option<&str>) -> Self {
+        self.node_id = node_id.map(str::to_string);
+        self
+    }
+
+    /// The kind of the error, depending on the class of the exception:
+    /// - `NotImplementedError` is `Unimplemented`,
+    /// - `ImportError` and `FileNotFoundError` are `NotFound`,
+    /// - `ConnectionError` is `Disconnected`, other `OSError`s are `IOError`,
+    /// - `ValueError` and `TypeError` are `InvalidData`,
+    /// - other exceptions are `GenericError`.
+    pub fn kind(&self) -> &ErrorKind {
+        &self.kind
+    }
+
+    /// Converts the exception into a `ZFError` of its [`kind`](Self::kind), whose source is the
+    /// exception.
+    pub fn into_zferr(self) -> Error {
+        let summary = match &self.node_id {
+            Some(node_id) => format!("{self:?} (node {node_id})"),
+            None => format!("{self:?}"),
+        };
+        zferror!(self.kind.clone(), self => "{summary}").into()
+    }
+}
+
+fn extract_frames(py: Python, traceback: &PyAny) -> PyResult<Vec<PythonFrame>> {
+    py.import("traceback")?
+        .call_method1("extract_tb", (traceback,))?
+        .iter()?
+        .map(|frame| {
+            let frame = frame?;
+            Ok(PythonFrame {
+                file: frame.getattr("filename")?.extract()?,
+                line: frame
+                    .getattr("lineno")?
+                    .extract::<Option<usize>>()?
+                    .unwrap_or_default(),
+                function: frame.getattr("name")?.extract()?,
+            })
+        })
+        .collect()
+}
+
+/// Renders the exception like Python does, preceded by the node that raised it.
+impl fmt::Display for PythonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(node_id) = &self.node_id {
+            writeln!(f, "Python node {node_id} raised:")?;
+        }
+        if !self.frames.is_empty() {
+            writeln!(f, "Traceback (most recent call last):")?;
+            for frame in &self.frames {
+                writeln!(
+                    f,
+                    "  File \"{}\", line {}, in {}",
+                    frame.file, frame.line, frame.function
+                )?;
+            }
+        }
+        write!(f, "{self:?}")
+    }
+}
+
+/// The class and the message of the exception, e.g. `ValueError: invalid literal`, as `ZFError`
+/// embeds the `Debug` rendering of its source in its own.
+impl fmt::Debug for PythonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.message.is_empty() {
+            write!(f, "{}", self.exception)
+        } else {
+            write!(f, "{}: {}", self.exception, self.message)
+        }
+    }
+}
+
+impl std::error::Error for PythonError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kind_of(py: Python, raise: &str) -> ErrorKind {
+        let err = py.run(raise, None, None).unwrap_err();
+        PythonError::new(py, &err).kind().clone()
+    }
+
+    #[test]
+    fn kinds_follow_the_exception_classes() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            for (raise, kind) in [
+                ("raise NotImplementedError()", ErrorKind::Unimplemented),
+                ("raise ImportError('no module')", ErrorKind::NotFound),
+                (
+                    "raise ModuleNotFoundError('no module')",
+                    ErrorKind::NotFound,
+                ),
+                ("raise FileNotFoundError('no file')", ErrorKind::NotFound),
+                ("raise ConnectionRefusedError()", ErrorKind::Disconnected),
+                ("raise PermissionError()", ErrorKind::IOError),
+                ("raise OSError()", ErrorKind::IOError),
+                ("raise ValueError()", ErrorKind::InvalidData),
+                (
+                    "raise UnicodeDecodeError('utf-8', b'', 0, 1, 'bad')",
+                    ErrorKind::InvalidData,
+                ),
+                ("raise TypeError()", ErrorKind::InvalidData),
+                ("raise RuntimeError()", ErrorKind::GenericError),
+                (
+                    "class CustomError(ValueError): pass\nraise CustomError()",
+                    ErrorKind::InvalidData,
+                ),
+            ] {
+                assert_eq!(
+                    format!("{:?}", kind_of(py, raise)),
+                    format!("{:?}", kind),
+                    "{}",
+                    raise
+                );
+            }
+        });
+    }
+
+    #[test]
+    fn exceptions_are_rendered_like_python() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let err = py
+                .run("def f():\n    raise ValueError('bad')\nf()", None, None)
+                .unwrap_err();
+            let err = PythonError::new(py, &err).with_node_id(Some("detector"));
+
+            assert_eq!(err.exception, "ValueError");
+            assert_eq!(err.message, "bad");
+            assert_eq!(format!("{:?}", err), "ValueError: bad");
+            assert_eq!(err.frames.last().unwrap().function, "f");
+            let rendered = err.to_string();
+            assert!(rendered.starts_with("Python node detector raised:\nTraceback"));
+            assert!(rendered.ends_with("ValueError: bad"));
+
+            let err = err.into_zferr();
+            assert!(err.to_string().contains("ValueError: bad (node detector)"));
+        });
+    }
+}
@@ -0,0 +1,191 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+//! The `finalize` of Python nodes, called when they are destroyed.
+//!
+//! A regular `finalize` method is called on the thread destroying the node, which waits for it to
+//! complete. An `async def` coroutine is run on the event loop of the node and cancelled if it
+//! does not complete within the `finalize-timeout` of the node configuration, in seconds,
+//! [`FINALIZE_TIMEOUT`] by default. That key is removed from the configuration given to the Python
+//! node.
+//!
+//! Nothing can be done about a failing `finalize`: its exception is logged, with its traceback,
+//! and the node is destroyed anyway.
+
+use crate::PythonError;
+use pyo3::prelude::*;
+use std::time::Duration;
+use zenoh_flow::prelude::{zferror, Configuration, ErrorKind, Result};
+
+/// How long we wait, by default, for the `async def finalize` of a node to complete.
+pub const FINALIZE_TIMEOUT: Duration = Duration::from_secs(5);
+
+const FINALIZE_TIMEOUT_KEY: &str = "finalize-timeout";
+
+/// Takes the `finalize-timeout` key, in seconds, out of the `configuration`.
+///
+/// # Errors
+///
+/// This function fails if the `finalize-timeout` is not a positive number.
+pub(crate) fn take_finalize_timeout(configuration: &mut Configuration) -> Result<Duration> {
+    let timeout = match configuration
+        .as_object_mut()
+        .and_then(|configuration| configuration.remove(FINALIZE_TIMEOUT_KEY))
+    {
+        Some(timeout) => timeout,
+        None => return Ok(FINALIZE_TIMEOUT),
+    };
+
+    timeout
+        .as_f64()
+        .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+        .ok_or_else(|| {
+            zferror!(
+                ErrorKind::ConfigurationError,
+                "{FINALIZE_TIMEOUT_KEY} must be a positive number of seconds, found {timeout}"
+            )
+            .into()
+        })
+}
+
+/// Calls the `finalize` of `node`, running the awaitable it returns, if any, on its `event_loop`,
+/// which must not be running, for at most `timeout`. The errors are logged instead of returned.
+pub(crate) fn finalize(
+    py: Python,
+    asyncio: &PyAny,
+    event_loop: &PyAny,
+    node: &PyAny,
+    timeout: Duration,
+    node_id: Option<&str>,
+) {
+    let finalized = (|| {
+        if !node.hasattr("finalize")? {
+            return Ok(());
+        }
+
+        let result = node.call_method0("finalize")?;
+        if py
+            .import("inspect")?
+            .call_method1("isawaitable", (result,))?
+            .is_true()?
+        {
+            let bounded = asyncio.call_method1("wait_for", (result, timeout.as_secs_f64()))?;
+            event_loop.call_method1("run_until_complete", (bounded,))?;
+        }
+        Ok::<_, PyErr>(())
+    })();
+
+    if let Err(e) = finalized {
+        let timed_out = asyncio
+            .getattr("TimeoutError")
+            .map_or(false, |timeout_error| e.matches(py, timeout_error));
+        if timed_out {
+            log::warn!(
+                "The finalize of the Python node{} did not complete within {:?}, moving on",
+                node_id.map(|id| format!(" {id}")).unwrap_or_default(),
+                timeout
+            );
+        } else {
+            log::error!(
+                "The finalize of the Python node failed:\n{}",
+                PythonError::new(py, &e).with_node_id(node_id)
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    const NODES: &str = r#"
+import asyncio
+import threading
+
+class SyncNode:
+    def finalize(self):
+        self.thread = threading.get_ident()
+
+class SlowNode:
+    async def finalize(self):
+        self.started = True
+        await asyncio.sleep(3600)
+"#;
+
+    #[test]
+    fn sync_finalize_runs_on_the_node_thread() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let module = PyModule::from_code(py, NODES, "nodes.py", "nodes").unwrap();
+            let asyncio = py.import("asyncio").unwrap();
+            let event_loop = asyncio.call_method0("new_event_loop").unwrap();
+            let node = module.getattr("SyncNode").unwrap().call0().unwrap();
+
+            finalize(py, asyncio, event_loop, node, FINALIZE_TIMEOUT, None);
+
+            let thread: u64 = node.getattr("thread").unwrap().extract().unwrap();
+            let current: u64 = py
+                .import("threading")
+                .unwrap()
+                .call_method0("get_ident")
+                .unwrap()
+                .extract()
+                .unwrap();
+            assert_eq!(thread, current);
+        });
+    }
+
+    #[test]
+    fn async_finalize_is_bounded_by_the_timeout() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let module = PyModule::from_code(py, NODES, "nodes.py", "nodes").unwrap();
+            let asyncio = py.import("asyncio").unwrap();
+            let event_loop = asyncio.call_method0("new_event_loop").unwrap();
+            let node = module.getattr("SlowNode").unwrap().call0().unwrap();
+
+            let start = Instant::now();
+            finalize(
+                py,
+                asyncio,
+                event_loop,
+                node,
+                Duration::from_millis(100),
+                None,
+            );
+            assert!(start.elapsed() < Duration::from_secs(5));
+            assert!(node.getattr("started").unwrap().is_true().unwrap());
+        });
+    }
+
+    #[test]
+    fn finalize_timeout_is_read_from_the_configuration() {
+        let mut configuration = serde_json::json!({ "finalize-timeout": 0.5, "other": 1 });
+        assert_eq!(
+            take_finalize_timeout(&mut configuration).unwrap(),
+            Duration::from_millis(500)
+        );
+        assert_eq!(configuration, serde_json::json!({ "other": 1 }));
+        assert_eq!(
+            take_finalize_timeout(&mut configuration).unwrap(),
+            FINALIZE_TIMEOUT
+        );
+
+        for invalid in [serde_json::json!(-1), serde_json::json!("5s")] {
+            let mut configuration = serde_json::json!({ "finalize-timeout": invalid });
+            assert!(take_finalize_timeout(&mut configuration).is_err());
+        }
+    }
+}
@@ -16,20 +16,52 @@
 // This allow is needed for a false positive
 // when using &PyBytes as function parameter.
 
-use pyo3::exceptions::{PyTypeError, PyValueError};
+mod arrow;
+mod buffer;
+mod codec;
+mod error;
+mod finalize;
+mod lifecycle;
+mod loader;
+mod ndarray;
+mod node;
+mod ports;
+mod protobuf;
+mod run;
+mod schema;
+mod select;
+pub use buffer::PyPayload;
+pub use codec::Codec;
+pub use error::{PythonError, PythonFrame};
+pub use finalize::FINALIZE_TIMEOUT;
+pub use loader::load_node_class;
+pub use node::{NodeKind, PythonNode};
+pub use ports::{PortType, PortTypes};
+pub use protobuf::ProtobufCodec;
+pub use run::RunTask;
+pub use schema::{configuration_schema, validate_configuration};
+pub use select::RawSelect;
+
+use buffer::BufferInner;
+use pyo3::create_exception;
+use pyo3::exceptions::{PyStopAsyncIteration, PyTimeoutError, PyTypeError, PyValueError};
 use pyo3::prelude::*;
-use pyo3::types::{PyBytes, PyDict, PyList, PyLong, PyString};
+use pyo3::types::{PyBool, PyDict, PyFloat, PyList, PyLong, PyString, PyTuple};
 use std::convert::{TryFrom, TryInto};
 use zenoh_flow::bail;
 
+use uhlc::Timestamp;
 use zenoh_flow::prelude::{
     zferror, Configuration, Context as ZFContext, Error, ErrorKind, InputRaw as ZInput, Inputs,
     OutputRaw as ZOutput, Outputs,
 };
 use zenoh_flow::types::LinkMessage as ZFMessage;
 use zenoh_flow::types::Payload;
+use zenoh_flow::zfresult::ZFError;
 
-use std::sync::Arc;
+use pyo3_asyncio::TaskLocals;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 #[derive(Clone)]
 pub struct PythonState {
@@ -37,6 +69,117 @@ pub struct PythonState {
     pub py_state: Arc<PyObject>,
     pub event_loop: Arc<PyObject>,
     pub asyncio_module: Arc<PyObject>,
+    pub run_task: Arc<Mutex<Option<RunTask>>>,
+    /// Whether the node defines an `async def run(self)` coroutine, called instead of `iteration`.
+    pub has_run: bool,
+    /// How long the `async def finalize` of the node is waited for, see [`FINALIZE_TIMEOUT`].
+    pub finalize_timeout: Duration,
+    pub node_id: Option<String>,
+}
+
+impl PythonState {
+    /// Drives the Python node, calling its `on_start` hook first and its `on_stop` hook when the
+    /// returned future completes or is dropped, see the [`lifecycle`] module.
+    ///
+    /// A node defining an `async def run(self)` coroutine opts into a single, long-lived, call: its
+    /// event loop is started in a dedicated thread on which `run` is scheduled, once. When `run`
+    /// returns the node has nothing left to do and this future never completes. Dropping the future,
+    /// i.e. stopping the node, cancels `run` before calling `on_stop`, see the [`run`] module.
+    ///
+    /// Other nodes have their `iteration` coroutine called, and their event loop run until it
+    /// completes, in a loop. This future only completes if an iteration fails.
+    pub async fn iteration(&self) -> zenoh_flow::Result<()> {
+        Python::with_gil(|py| {
+            lifecycle::call_hook(
+                py,
+                self.asyncio_module.cast_as::<PyAny>(py)?,
+                self.event_loop.cast_as::<PyAny>(py)?,
+                self.py_state.cast_as::<PyAny>(py)?,
+                lifecycle::ON_START,
+                lifecycle::HOOK_TIMEOUT,
+            )
+        })
+        .map_err(|e| self.zferr(e))?;
+
+        let _stop = lifecycle::StopGuard {
+            asyncio: &self.asyncio_module,
+            event_loop: &self.event_loop,
+            node: &self.py_state,
+            run_task: &self.run_task,
+            node_id: self.node_id.as_deref(),
+        };
+
+        if self.has_run {
+            return self.run().await;
+        }
+
+        loop {
+            self.iterate()?;
+            async_std::task::yield_now().await;
+        }
+    }
+
+    /// Calls the `iteration` coroutine of the node and runs its event loop until it completes.
+    fn iterate(&self) -> zenoh_flow::Result<()> {
+        Python::with_gil(|py| {
+            let node = self.py_state.cast_as::<PyAny>(py)?;
+
+            let event_loop = self.event_loop.cast_as::<PyAny>(py)?;
+
+            let task_locals = TaskLocals::new(event_loop);
+
+            let py_future = node.call_method0("iteration")?;
+
+            let fut = pyo3_asyncio::into_future_with_locals(&task_locals, py_future)?;
+            pyo3_asyncio::async_std::run_until_complete(event_loop, fut)
+        })
+        .map_err(|e| self.zferr(e))?;
+        Ok(())
+    }
+
+    async fn run(&self) -> zenoh_flow::Result<()> {
+        let done = Python::with_gil(|py| {
+            let mut run_task = self.run_task.lock().map_err(|e| {
+                zferror!(ErrorKind::GenericError, "Python run task poisoned: {}", e)
+            })?;
+            if run_task.is_none() {
+                *run_task = Some(
+                    RunTask::spawn(
+                        py,
+                        self.asyncio_module
+                            .cast_as::<PyAny>(py)
+                            .map_err(from_pydwncasterr_to_zferr)?,
+                        self.event_loop
+                            .cast_as::<PyAny>(py)
+                            .map_err(from_pydwncasterr_to_zferr)?,
+                        self.py_state
+                            .cast_as::<PyAny>(py)
+                            .map_err(from_pydwncasterr_to_zferr)?,
+                    )
+                    .map_err(|e| self.zferr(e))?,
+                );
+            }
+            Ok::<_, Error>(run_task.as_ref().map(RunTask::done))
+        })?;
+
+        match done {
+            Some(done) => match done.recv_async().await {
+                Ok(outcome) => outcome.map_err(|e| self.zferr(e)),
+                // `run` already returned: there is nothing left to do.
+                Err(_) => async_std::future::pending().await,
+            },
+            None => Ok(()),
+        }
+    }
+
+    /// Converts the exception `e`, raised by the node, into an error.
+    fn zferr(&self, e: PyErr) -> Error {
+        Python::with_gil(|py| {
+            PythonError::new(py, &e)
+                .with_node_id(self.node_id.as_deref())
+                .into_zferr()
+        })
+    }
 }
 
 impl Drop for PythonState {
@@ -44,14 +187,26 @@ impl Drop for PythonState {
         let gil = Python::acquire_gil();
         let py = gil.python();
 
-        let py_op = self
-            .py_state
-            .cast_as::<PyAny>(py)
-            .expect("Unable to get Python Node module!");
-
-        py_op
-            .call_method0("finalize")
-            .expect("Unable to call Python finalize!");
+        let finalizing = (|| {
+            let asyncio = self.asyncio_module.cast_as::<PyAny>(py)?;
+            let event_loop = self.event_loop.cast_as::<PyAny>(py)?;
+            let node = self.py_state.cast_as::<PyAny>(py)?;
+            Ok::<_, pyo3::PyDowncastError>((asyncio, event_loop, node))
+        })();
+        match finalizing {
+            Ok((asyncio, event_loop, node)) => {
+                run::stop_run_task(py, &self.run_task, asyncio, event_loop);
+                finalize::finalize(
+                    py,
+                    asyncio,
+                    event_loop,
+                    node,
+                    self.finalize_timeout,
+                    self.node_id.as_deref(),
+                )
+            }
+            Err(e) => log::error!("Unable to finalize the Python node: {e}"),
+        }
     }
 }
 
@@ -64,20 +219,9 @@ impl std::fmt::Debug for PythonState {
     }
 }
 
+/// Converts the exception `py_err` into an error, see [`PythonError`].
 pub fn from_pyerr_to_zferr(py_err: pyo3::PyErr, py: &pyo3::Python<'_>) -> Error {
-    let tb = if let Some(traceback) = py_err.traceback(*py) {
-        traceback.format().map_or_else(|_| "".to_string(), |s| s)
-    } else {
-        "".to_string()
-    };
-
-    zferror!(
-        ErrorKind::InvalidData,
-        "Error: {:?}\nTraceback: {:?}",
-        py_err,
-        tb
-    )
-    .into()
+    PythonError::new(*py, &py_err).into_zferr()
 }
 
 pub fn from_pydwncasterr_to_zferr(py_err: pyo3::PyDowncastError) -> Error {
@@ -97,6 +241,23 @@ pub fn context_into_py<'p>(py: &'p Python, ctx: &ZFContext) -> PyResult<&'p PyAn
     Ok(py_ctx)
 }
 
+/// Converts the HLC [`Timestamp`](`Timestamp`) into a `zenoh_flow.types.Timestamp`, keeping both
+/// its NTP64 time and the ID of its producer.
+pub fn timestamp_into_py(py: Python, ts: &Timestamp) -> PyResult<PyObject> {
+    timestamp_into_py_with(py, timestamp_class(py)?, ts)
+}
+
+/// Returns the `zenoh_flow.types.Timestamp` class, to be looked up once when converting several
+/// timestamps.
+fn timestamp_class(py: Python) -> PyResult<&PyAny> {
+    PyModule::import(py, "zenoh_flow.types")?.getattr("Timestamp")
+}
+
+fn timestamp_into_py_with(py: Python, class: &PyAny, ts: &Timestamp) -> PyResult<PyObject> {
+    let py_ts = class.call1((ts.get_time().as_u64(), format!("{}", ts.get_id())))?;
+    Ok(py_ts.to_object(py))
+}
+
 pub fn configuration_into_py(py: Python, value: Configuration) -> PyResult<PyObject> {
     match value {
         Configuration::Array(arr) => {
@@ -149,10 +310,73 @@ pub fn configuration_into_py(py: Python, value: Configuration) -> PyResult<PyObj
     }
 }
 
-pub fn inputs_into_py(py: Python, mut inputs: Inputs) -> PyResult<PyObject> {
+/// Converts a Python object into a `Configuration`, the reverse of `configuration_into_py`.
+///
+/// Supported are `dict` (with `str` keys), `list`, `tuple`, `int` (within the `i64` and `u64`
+/// ranges), `float` (finite), `bool`, `str` and `None`, nested at will.
+pub fn configuration_from_py(obj: &PyAny) -> PyResult<Configuration> {
+    if obj.is_none() {
+        Ok(Configuration::Null)
+    } else if let Ok(b) = obj.downcast::<PyBool>() {
+        Ok(Configuration::Bool(b.is_true()))
+    } else if obj.is_instance_of::<PyLong>()? {
+        if let Ok(i) = obj.extract::<i64>() {
+            Ok(Configuration::from(i))
+        } else if let Ok(u) = obj.extract::<u64>() {
+            Ok(Configuration::from(u))
+        } else {
+            Err(PyValueError::new_err(format!(
+                "Unable to convert {obj} to Configuration: out of the i64 and u64 ranges"
+            )))
+        }
+    } else if let Ok(f) = obj.downcast::<PyFloat>() {
+        serde_json::Number::from_f64(f.value())
+            .map(Configuration::Number)
+            .ok_or_else(|| {
+                PyValueError::new_err(format!(
+                    "Unable to convert {obj} to Configuration: not a finite number"
+                ))
+            })
+    } else if let Ok(s) = obj.downcast::<PyString>() {
+        Ok(Configuration::String(s.to_str()?.to_string()))
+    } else if let Ok(l) = obj.downcast::<PyList>() {
+        l.iter()
+            .map(configuration_from_py)
+            .collect::<PyResult<Vec<_>>>()
+            .map(Configuration::Array)
+    } else if let Ok(t) = obj.downcast::<PyTuple>() {
+        t.iter()
+            .map(configuration_from_py)
+            .collect::<PyResult<Vec<_>>>()
+            .map(Configuration::Array)
+    } else if let Ok(d) = obj.downcast::<PyDict>() {
+        let mut map = serde_json::Map::with_capacity(d.len());
+        for (k, v) in d.iter() {
+            let key = k.downcast::<PyString>().map_err(|_| {
+                PyTypeError::new_err(format!(
+                    "Unable to convert {d} to Configuration: key {k} is not a str"
+                ))
+            })?;
+            map.insert(key.to_str()?.to_string(), configuration_from_py(v)?);
+        }
+        Ok(Configuration::Object(map))
+    } else {
+        Err(PyTypeError::new_err(format!(
+            "Unable to convert {obj} to Configuration: unsupported type {}",
+            obj.get_type().name()?
+        )))
+    }
+}
+
+pub fn inputs_into_py(
+    py: Python,
+    mut inputs: Inputs,
+    port_types: &PortTypes,
+) -> PyResult<PyObject> {
     let py_zenoh_flow = py.import("zenoh_flow")?;
 
     let py_receivers = PyDict::new(py);
+    let py_port_types = PyDict::new(py);
     let inputs_ids = inputs.keys().cloned().collect::<Vec<_>>();
     for id in &inputs_ids {
         let input = inputs
@@ -162,16 +386,26 @@ pub fn inputs_into_py(py: Python, mut inputs: Inputs) -> PyResult<PyObject> {
 
         let pyo3_rx = RawInput::from(input);
         py_receivers.set_item(PyString::new(py, id), &pyo3_rx.into_py(py))?;
+        if let Some(port_type) = port_types.get(id) {
+            py_port_types.set_item(PyString::new(py, id), port_type.clone().into_py(py))?;
+        }
     }
 
-    let py_inputs = py_zenoh_flow.getattr("Inputs")?.call1((py_receivers,))?;
+    let py_inputs = py_zenoh_flow
+        .getattr("Inputs")?
+        .call1((py_receivers, py_port_types))?;
     Ok(py_inputs.to_object(py))
 }
 
-pub fn outputs_into_py(py: Python, mut outputs: Outputs) -> PyResult<PyObject> {
+pub fn outputs_into_py(
+    py: Python,
+    mut outputs: Outputs,
+    port_types: &PortTypes,
+) -> PyResult<PyObject> {
     let py_zenoh_flow = py.import("zenoh_flow")?;
 
     let py_senders = PyDict::new(py);
+    let py_port_types = PyDict::new(py);
     let outputs_ids = outputs.keys().cloned().collect::<Vec<_>>();
     for id in &outputs_ids {
         let output = outputs
@@ -180,12 +414,63 @@ pub fn outputs_into_py(py: Python, mut outputs: Outputs) -> PyResult<PyObject> {
             .raw();
         let pyo3_tx = RawOutput::from(output);
         py_senders.set_item(PyString::new(py, id), &pyo3_tx.into_py(py))?;
+        if let Some(port_type) = port_types.get(id) {
+            py_port_types.set_item(PyString::new(py, id), port_type.clone().into_py(py))?;
+        }
     }
 
-    let py_outputs = py_zenoh_flow.getattr("Outputs")?.call1((py_senders,))?;
+    let py_outputs = py_zenoh_flow
+        .getattr("Outputs")?
+        .call1((py_senders, py_port_types))?;
     Ok(py_outputs.to_object(py))
 }
 
+/// Checks, once the Python node `node_id` is created, that it took all the `inputs` and `outputs`
+/// it was given, and none that it was not given.
+///
+/// # Errors
+///
+/// This function fails with an error naming the ports never taken and the unknown ports taken.
+pub fn check_ports_taken(
+    py: Python,
+    inputs: Option<&PyObject>,
+    outputs: Option<&PyObject>,
+    node_id: Option<&str>,
+) -> zenoh_flow::Result<()> {
+    let zferr = |e: PyErr| PythonError::new(py, &e).with_node_id(node_id).into_zferr();
+    let mut problems = Vec::new();
+    for (kind, ports) in [("inputs", inputs), ("outputs", outputs)] {
+        if let Some(ports) = ports {
+            let untaken: Vec<String> = ports
+                .call_method0(py, "_untaken")
+                .and_then(|ids| ids.extract(py))
+                .map_err(zferr)?;
+            let unknown: Vec<String> = ports
+                .call_method0(py, "_unknown")
+                .and_then(|ids| ids.extract(py))
+                .map_err(zferr)?;
+
+            if !untaken.is_empty() {
+                problems.push(format!("{kind} never taken: {}", untaken.join(", ")));
+            }
+            if !unknown.is_empty() {
+                problems.push(format!("unknown {kind} taken: {}", unknown.join(", ")));
+            }
+        }
+    }
+
+    if !problems.is_empty() {
+        bail!(
+            ErrorKind::ConfigurationError,
+            "The Python node{} does not match its descriptor, {}",
+            node_id.map(|id| format!(" {id}")).unwrap_or_default(),
+            problems.join("; ")
+        );
+    }
+
+    Ok(())
+}
+
 /// Channels that sends data to downstream nodes.
 #[pyclass]
 pub struct RawOutput {
@@ -196,6 +481,10 @@ pub struct RawOutput {
 impl RawOutput {
     /// Send, *asynchronously*, the bytes on all channels.
     ///
+    /// `data` can be any object implementing the buffer protocol (`bytes`, `bytearray`,
+    /// `memoryview`, NumPy array, …), its content is not copied. A mutable object should not be
+    /// modified after being sent.
+    ///
     /// If no timestamp is provided, the current timestamp — as per the HLC — is taken.
     ///
     /// If an error occurs while sending the message on a channel, we still try to send it on the
@@ -203,16 +492,93 @@ impl RawOutput {
     pub fn send<'p>(
         &'p self,
         py: Python<'p>,
-        data: &PyBytes,
+        data: &PyAny,
+        ts: Option<u64>,
+    ) -> PyResult<&'p PyAny> {
+        self.send_payload(py, Payload::from(PyPayload::new(data)?), ts)
+    }
+
+    /// Send, *asynchronously* and sequentially, the `(data, ts)` items on all channels.
+    ///
+    /// The whole list is handed over in a single call. Returns a list with, for each item, `None`
+    /// if it was sent or the reason why it was not: a failing item does not prevent the following
+    /// ones from being sent.
+    pub fn send_batch<'p>(
+        &'p self,
+        py: Python<'p>,
+        items: Vec<(&PyAny, Option<u64>)>,
+    ) -> PyResult<&'p PyAny> {
+        let items = items
+            .into_iter()
+            .map(|(data, ts)| {
+                PyPayload::new(data)
+                    .map(|payload| (Payload::from(payload), ts))
+                    .map_err(|e| e.to_string())
+            })
+            .collect::<Vec<_>>();
+
+        let c_sender = self.sender.clone();
+        pyo3_asyncio::async_std::future_into_py(py, async move {
+            let mut failures = Vec::with_capacity(items.len());
+            for item in items {
+                failures.push(match item {
+                    Ok((payload, ts)) => c_sender
+                        .send(payload, ts)
+                        .await
+                        .err()
+                        .map(|e| e.to_string()),
+                    Err(e) => Some(e),
+                });
+            }
+            Ok(failures)
+        })
+    }
+
+    /// Send, *asynchronously*, the NumPy array on all channels.
+    ///
+    /// The array is encoded along with its dtype, shape and strides, and can be received with
+    /// `RawInput.recv_ndarray`. Its data is not copied if it is C-contiguous, a contiguous copy is
+    /// sent otherwise. The array should not be modified after being sent.
+    ///
+    /// If no timestamp is provided, the current timestamp — as per the HLC — is taken.
+    pub fn send_ndarray<'p>(
+        &'p self,
+        py: Python<'p>,
+        array: &PyAny,
         ts: Option<u64>,
     ) -> PyResult<&'p PyAny> {
+        self.send_payload(py, ndarray::encode(array)?, ts)
+    }
+
+    /// Send, *asynchronously*, the `pyarrow.RecordBatch` on all channels.
+    ///
+    /// The batch is sent in the Arrow IPC streaming format, it can be received with
+    /// `RawInput.recv_arrow` or read by Rust nodes with an Arrow IPC `StreamReader`. It is only
+    /// serialized if it is sent to a node that is not a Python node running in the same process.
+    ///
+    /// If no timestamp is provided, the current timestamp — as per the HLC — is taken.
+    pub fn send_arrow<'p>(
+        &'p self,
+        py: Python<'p>,
+        batch: &PyAny,
+        ts: Option<u64>,
+    ) -> PyResult<&'p PyAny> {
+        self.send_payload(py, arrow::encode(batch)?, ts)
+    }
+
+    /// Send, *asynchronously*, a watermark on all channels.
+    ///
+    /// A watermark signals that no message with a lower timestamp will be sent afterwards on this
+    /// Output. A `ValueError` is raised if `ts` is lower than the last watermark sent.
+    ///
+    /// If no timestamp is provided, the current timestamp — as per the HLC — is taken.
+    pub fn send_watermark<'p>(&'p self, py: Python<'p>, ts: Option<u64>) -> PyResult<&'p PyAny> {
         let c_sender = self.sender.clone();
-        let rust_data = Payload::from(data.as_bytes());
         pyo3_asyncio::async_std::future_into_py(py, async move {
             c_sender
-                .send(rust_data, ts)
+                .send_watermark(ts)
                 .await
-                .map_err(|_| PyValueError::new_err("Unable to send data"))?;
+                .map_err(|e| PyValueError::new_err(format!("Unable to send watermark: {e}")))?;
             Ok(Python::with_gil(|py| py.None()))
         })
     }
@@ -224,6 +590,24 @@ impl RawOutput {
     }
 }
 
+impl RawOutput {
+    fn send_payload<'p>(
+        &'p self,
+        py: Python<'p>,
+        payload: Payload,
+        ts: Option<u64>,
+    ) -> PyResult<&'p PyAny> {
+        let c_sender = self.sender.clone();
+        pyo3_asyncio::async_std::future_into_py(py, async move {
+            c_sender
+                .send(payload, ts)
+                .await
+                .map_err(|_| PyValueError::new_err("Unable to send data"))?;
+            Ok(Python::with_gil(|py| py.None()))
+        })
+    }
+}
+
 impl From<ZOutput> for RawOutput {
     fn from(other: ZOutput) -> Self {
         Self {
@@ -240,6 +624,13 @@ impl From<&ZOutput> for RawOutput {
     }
 }
 
+create_exception!(
+    zenoh_flow,
+    RecvTimeoutError,
+    PyTimeoutError,
+    "Raised when no message is received on an Input before the timeout expires."
+);
+
 /// Channels that receives data from upstream nodes.
 #[pyclass(subclass)]
 pub struct RawInput {
@@ -252,17 +643,131 @@ impl RawInput {
     /// associated with this Input.
     ///
     /// If several `RawDataMessage` are received at the same time, one is randomly selected.
-    pub fn recv<'p>(&'p self, py: Python<'p>) -> PyResult<&'p PyAny> {
+    ///
+    /// If a `timeout`, in seconds, is provided and no message is received before it expires, a
+    /// `RecvTimeoutError` is raised.
+    #[args(timeout = "None")]
+    pub fn recv<'p>(&'p self, py: Python<'p>, timeout: Option<f64>) -> PyResult<&'p PyAny> {
+        self.recv_with(py, timeout, decode_buffer)
+    }
+
+    /// Returns, *asynchronously*, a list of at most `max_items` `RawDataMessage` received on the
+    /// channels associated with this Input.
+    ///
+    /// The messages already queued are drained without waiting. If `max_wait`, in seconds, is
+    /// provided, messages are awaited until `max_items` are received or `max_wait` expires — the
+    /// list is empty if no message was received in the meantime. Otherwise, only the first message
+    /// is awaited.
+    ///
+    /// All the messages are converted under a single acquisition of the GIL.
+    #[args(max_wait = "None")]
+    pub fn recv_batch<'p>(
+        &'p self,
+        py: Python<'p>,
+        max_items: usize,
+        max_wait: Option<f64>,
+    ) -> PyResult<&'p PyAny> {
+        if max_items == 0 {
+            return Err(PyValueError::new_err("max_items must be greater than 0"));
+        }
+        let deadline = max_wait
+            .map(duration_from_secs)
+            .transpose()?
+            .map(|max_wait| Instant::now() + max_wait);
+
         let c_receiver = self.receiver.clone();
         pyo3_asyncio::async_std::future_into_py(py, async move {
-            let rust_msg = c_receiver
-                .recv()
-                .await
-                .map_err(|_| PyValueError::new_err("Unable to receive data"))?;
-            RawMessage::try_from(rust_msg)
+            let mut rust_msgs = Vec::with_capacity(max_items);
+            while rust_msgs.len() < max_items {
+                if let Ok(rust_msg) = c_receiver.try_recv() {
+                    rust_msgs.push(rust_msg);
+                    continue;
+                }
+
+                let received = match deadline {
+                    None if rust_msgs.is_empty() => c_receiver.recv().await,
+                    None => break,
+                    Some(deadline) => match async_std::future::timeout(
+                        deadline.saturating_duration_since(Instant::now()),
+                        c_receiver.recv(),
+                    )
+                    .await
+                    {
+                        Ok(received) => received,
+                        Err(_) => break,
+                    },
+                };
+
+                match received {
+                    Ok(rust_msg) => rust_msgs.push(rust_msg),
+                    Err(_) if !rust_msgs.is_empty() => break,
+                    Err(_) => return Err(PyValueError::new_err("Unable to receive data")),
+                }
+            }
+
+            Python::with_gil(|py| {
+                let timestamp_class = timestamp_class(py)?;
+                rust_msgs
+                    .into_iter()
+                    .map(|rust_msg| {
+                        RawMessage::from_link_message(py, timestamp_class, rust_msg, decode_buffer)
+                    })
+                    .collect::<PyResult<Vec<_>>>()
+            })
         })
     }
 
+    /// Returns the first `RawDataMessage` that was received on any of the channels associated with
+    /// this Input, or `None` if all the channels are empty.
+    ///
+    /// This method does not block.
+    pub fn try_recv(&self) -> PyResult<Option<RawMessage>> {
+        match self.receiver.try_recv() {
+            Ok(rust_msg) => RawMessage::try_from(rust_msg).map(Some),
+            Err(e) => match e.downcast_ref::<ZFError>().map(|e| e.get_kind()) {
+                Some(ErrorKind::Empty) => Ok(None),
+                _ => Err(PyValueError::new_err(format!(
+                    "Unable to receive data: {e}"
+                ))),
+            },
+        }
+    }
+
+    /// Returns the first `RawDataMessage` that was received, *asynchronously*, on any of the channels
+    /// associated with this Input, its data being the NumPy array sent with
+    /// `RawOutput.send_ndarray`.
+    ///
+    /// The array is read-only and shares its memory with the received message.
+    #[args(timeout = "None")]
+    pub fn recv_ndarray<'p>(&'p self, py: Python<'p>, timeout: Option<f64>) -> PyResult<&'p PyAny> {
+        self.recv_with(py, timeout, ndarray::decode)
+    }
+
+    /// Returns the first `RawDataMessage` that was received, *asynchronously*, on any of the channels
+    /// associated with this Input, its data being the `pyarrow.RecordBatch` sent with
+    /// `RawOutput.send_arrow` (or by a Rust node, in the Arrow IPC streaming format).
+    #[args(timeout = "None")]
+    pub fn recv_arrow<'p>(&'p self, py: Python<'p>, timeout: Option<f64>) -> PyResult<&'p PyAny> {
+        self.recv_with(py, timeout, arrow::decode)
+    }
+
+    fn __aiter__(slf: PyRef<Self>) -> PyRef<Self> {
+        slf
+    }
+
+    /// Returns an awaitable on the next `RawDataMessage`, iteration stops when all the channels
+    /// associated with this Input are disconnected.
+    fn __anext__(&self, py: Python) -> PyResult<Option<PyObject>> {
+        let c_receiver = self.receiver.clone();
+        let py_future = pyo3_asyncio::async_std::future_into_py(py, async move {
+            match c_receiver.recv().await {
+                Ok(rust_msg) => RawMessage::try_from(rust_msg),
+                Err(_) => Err(PyStopAsyncIteration::new_err(())),
+            }
+        })?;
+        Ok(Some(py_future.into()))
+    }
+
     /// Returns the ID associated with this `Input`.
     pub fn port_id<'p>(&'p self, py: Python<'p>) -> PyResult<&'p PyString> {
         let port_id = self.receiver.port_id();
@@ -270,6 +775,40 @@ impl RawInput {
     }
 }
 
+impl RawInput {
+    fn recv_with<'p>(
+        &'p self,
+        py: Python<'p>,
+        timeout: Option<f64>,
+        decode: fn(Python, &Payload) -> PyResult<PyObject>,
+    ) -> PyResult<&'p PyAny> {
+        let timeout = timeout.map(duration_from_secs).transpose()?;
+
+        let c_receiver = self.receiver.clone();
+        pyo3_asyncio::async_std::future_into_py(py, async move {
+            let rust_msg = match timeout {
+                Some(timeout) => async_std::future::timeout(timeout, c_receiver.recv())
+                    .await
+                    .map_err(|_| {
+                        RecvTimeoutError::new_err(format!(
+                            "No message received on {} after {:?}",
+                            c_receiver.port_id(),
+                            timeout
+                        ))
+                    })?,
+                None => c_receiver.recv().await,
+            }
+            .map_err(|_| PyValueError::new_err("Unable to receive data"))?;
+            RawMessage::try_from_with(rust_msg, decode)
+        })
+    }
+}
+
+fn duration_from_secs(secs: f64) -> PyResult<Duration> {
+    Duration::try_from_secs_f64(secs)
+        .map_err(|e| PyValueError::new_err(format!("Invalid duration {secs}: {e}")))
+}
+
 impl From<ZInput> for RawInput {
     fn from(other: ZInput) -> Self {
         Self {
@@ -305,8 +844,8 @@ impl TryInto<ZInput> for RawInput {
 /// information whether the message is a `Watermark`
 #[pyclass(subclass)]
 pub struct RawMessage {
-    data: Py<PyBytes>,
-    ts: Py<PyLong>,
+    data: PyObject,
+    ts: PyObject,
     is_watermark: bool,
 }
 
@@ -314,24 +853,33 @@ pub struct RawMessage {
 impl RawMessage {
     /// Creates a new [`RawDataMessage`](`RawDataMessage`) with given bytes,
     ///  timestamp and watermark flag.
+    ///
+    /// `ts` is expected to be a `zenoh_flow.types.Timestamp`.
+    ///
+    /// `data` can be any object implementing the buffer protocol.
     #[new]
-    pub fn new(data: Py<PyBytes>, ts: Py<PyLong>, is_watermark: bool) -> Self {
-        Self {
+    pub fn new(py: Python, data: &PyAny, ts: PyObject, is_watermark: bool) -> PyResult<Self> {
+        let data = BufferInner::Python(Arc::new(PyPayload::new(data)?)).into_memoryview(py)?;
+        Ok(Self {
             data,
             ts,
             is_watermark,
-        }
+        })
     }
 
-    /// Returns a reference over bytes representing the data.
+    /// Returns a read-only `memoryview` over the data.
+    ///
+    /// The view is backed by the buffer that was received: no copy is performed. Use
+    /// `bytes(message.data)` to obtain a copy that outlives the message.
     #[getter]
-    pub fn get_data(&self) -> &Py<PyBytes> {
+    pub fn get_data(&self) -> &PyObject {
         &self.data
     }
 
-    /// Returns the data timestamp.
+    /// Returns the data timestamp, a `zenoh_flow.types.Timestamp` holding the NTP64 time and the
+    /// ID of the HLC that produced it.
     #[getter]
-    pub fn get_ts(&self) -> &Py<PyLong> {
+    pub fn get_ts(&self) -> &PyObject {
         &self.ts
     }
 
@@ -342,54 +890,173 @@ impl RawMessage {
     }
 }
 
+impl RawMessage {
+    /// Converts the [`LinkMessage`](`ZFMessage`), decoding the payload of data messages with
+    /// `decode`.
+    pub(crate) fn try_from_with(
+        other: ZFMessage,
+        decode: impl FnOnce(Python, &Payload) -> PyResult<PyObject>,
+    ) -> PyResult<Self> {
+        Python::with_gil(|py| Self::from_link_message(py, timestamp_class(py)?, other, decode))
+    }
+
+    /// Converts the [`LinkMessage`](`ZFMessage`) while holding the GIL, `timestamp_class` being
+    /// the `zenoh_flow.types.Timestamp` class.
+    fn from_link_message(
+        py: Python,
+        timestamp_class: &PyAny,
+        other: ZFMessage,
+        decode: impl FnOnce(Python, &Payload) -> PyResult<PyObject>,
+    ) -> PyResult<Self> {
+        match other {
+            ZFMessage::Data(msg) => Ok(Self {
+                data: decode(py, &msg)?,
+                ts: timestamp_into_py_with(py, timestamp_class, msg.get_timestamp())?,
+                is_watermark: false,
+            }),
+            ZFMessage::Watermark(ts) => Ok(Self {
+                data: BufferInner::Rust(Arc::default()).into_memoryview(py)?,
+                ts: timestamp_into_py_with(py, timestamp_class, &ts)?,
+                is_watermark: true,
+            }),
+        }
+    }
+}
+
+/// Returns a `memoryview` over the data of the [`Payload`](`Payload`).
+pub(crate) fn decode_buffer(py: Python, payload: &Payload) -> PyResult<PyObject> {
+    BufferInner::try_from_payload(payload)
+        .map_err(|e| PyValueError::new_err(format!("try_as_bytes field: {e}")))?
+        .into_memoryview(py)
+}
+
 impl TryFrom<ZFMessage> for RawMessage {
     type Error = PyErr;
 
     fn try_from(other: ZFMessage) -> Result<Self, Self::Error> {
-        match other {
-            ZFMessage::Data(msg) => {
-                let data = Python::with_gil(|py| {
-                    let bytes = msg
-                        .try_as_bytes()
-                        .map_err(|e| PyValueError::new_err(format!("try_as_bytes field: {e}")))?;
-
-                    Ok::<pyo3::Py<PyBytes>, Self::Error>(Py::from(PyBytes::new(py, bytes.as_ref())))
-                })?;
-
-                let ts: Py<PyLong> = Python::with_gil(|py| {
-                    Ok::<pyo3::Py<PyLong>, Self::Error>(Py::from(
-                        msg.get_timestamp()
-                            .get_time()
-                            .as_u64()
-                            .to_object(py)
-                            .cast_as::<PyLong>(py)?,
-                    ))
-                })?;
-
-                Ok(Self {
-                    data,
-                    ts,
-                    is_watermark: false,
-                })
+        Self::try_from_with(other, decode_buffer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn configuration_round_trip() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let configuration = json!({
+                "null": null,
+                "bool": true,
+                "int": -3,
+                "big": u64::MAX,
+                "float": 0.5,
+                "str": "zenoh",
+                "list": [1, "two", [3.0]],
+                "nested": { "empty": {} },
+            });
+            let py_config = configuration_into_py(py, configuration.clone()).unwrap();
+            assert_eq!(
+                configuration_from_py(py_config.as_ref(py)).unwrap(),
+                configuration
+            );
+
+            // Tuples are converted as lists.
+            let tuple = py.eval("(1, 'two')", None, None).unwrap();
+            assert_eq!(configuration_from_py(tuple).unwrap(), json!([1, "two"]));
+        });
+    }
+
+    /// Returns the `zenoh_flow.types` module of the Python sources, loaded against the classes of
+    /// this crate.
+    pub(crate) fn python_types(py: Python) -> &PyModule {
+        let modules = py.import("sys").unwrap().getattr("modules").unwrap();
+        let native = PyModule::new(py, "zenoh_flow").unwrap();
+        native.add_class::<RawOutput>().unwrap();
+        native.add_class::<RawInput>().unwrap();
+        native.add_class::<RawMessage>().unwrap();
+        native.add_class::<RawSelect>().unwrap();
+        native.add_class::<Codec>().unwrap();
+        native.add_class::<PortType>().unwrap();
+        modules.set_item("zenoh_flow", native).unwrap();
+        let types = PyModule::from_code(
+            py,
+            include_str!("../../zenoh-flow-python/zenoh_flow/types/__init__.py"),
+            "zenoh_flow/types/__init__.py",
+            "zenoh_flow_types",
+        );
+        modules.del_item("zenoh_flow").unwrap();
+        modules.del_item("zenoh_flow_types").ok();
+        types.unwrap()
+    }
+
+    #[test]
+    fn timestamps_are_compared_as_in_zenoh() {
+        use uhlc::{ID, NTP64};
+
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let class = python_types(py).getattr("Timestamp").unwrap();
+            // The IDs are displayed least significant byte first: "0102" is greater than "0201".
+            let greater = Timestamp::new(NTP64(1 << 32), ID::try_from([0x01, 0x02]).unwrap());
+            let lesser = Timestamp::new(NTP64(1 << 32), ID::try_from([0x02, 0x01]).unwrap());
+            assert!(lesser < greater);
+
+            let locals = PyDict::new(py);
+            locals.set_item("Timestamp", class).unwrap();
+            locals
+                .set_item(
+                    "greater",
+                    timestamp_into_py_with(py, class, &greater).unwrap(),
+                )
+                .unwrap();
+            locals
+                .set_item(
+                    "lesser",
+                    timestamp_into_py_with(py, class, &lesser).unwrap(),
+                )
+                .unwrap();
+            for expression in [
+                "greater.id == '0102' and lesser.id == '0201'",
+                "lesser < greater and not greater < lesser and lesser != greater",
+                "sorted([greater, lesser]) == [lesser, greater]",
+                "greater == Timestamp(greater.ntp, '010200')",
+                "len({greater, Timestamp(greater.ntp, '010200')}) == 1",
+            ] {
+                assert!(
+                    py.eval(expression, None, Some(locals))
+                        .unwrap()
+                        .extract::<bool>()
+                        .unwrap(),
+                    "{}",
+                    expression
+                );
             }
-            ZFMessage::Watermark(ts) => {
-                let data = Python::with_gil(|py| Py::from(PyBytes::new(py, &[0u8])));
-                let ts = Python::with_gil(|py| {
-                    Ok::<pyo3::Py<PyLong>, Self::Error>(Py::from(
-                        ts.get_time()
-                            .as_u64()
-                            .to_object(py)
-                            .cast_as::<PyLong>(py)
-                            .unwrap(),
-                    ))
-                })?;
-
-                Ok(Self {
-                    data,
-                    ts,
-                    is_watermark: true,
-                })
+        });
+    }
+
+    #[test]
+    fn unconvertible_python_values_are_rejected() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            for (obj, exception) in [
+                ("2 ** 64", "ValueError"),
+                ("float('nan')", "ValueError"),
+                ("{1: 'one'}", "TypeError"),
+                ("{'set': {1}}", "TypeError"),
+                ("object()", "TypeError"),
+            ] {
+                let err = configuration_from_py(py.eval(obj, None, None).unwrap()).unwrap_err();
+                assert_eq!(
+                    err.get_type(py).name().unwrap(),
+                    exception,
+                    "{}: {}",
+                    obj,
+                    err
+                );
             }
-        }
+        });
     }
 }
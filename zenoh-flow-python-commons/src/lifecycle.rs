@@ -0,0 +1,151 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+//! The `on_start` and `on_stop` hooks of Python nodes.
+//!
+//! Zenoh Flow does not notify the nodes it starts and stops: starting a node spawns a task calling
+//! its `iteration` in a loop, stopping it aborts that task. The wrappers thus call the Python
+//! iterations in a loop *within* a single Zenoh Flow iteration, which only completes on error:
+//! - `on_start` is called when that iteration begins, i.e. when the node is started or restarted,
+//! - `on_stop` is called when that iteration ends, i.e. when the runtime aborts it to stop the
+//!   node or when the node fails.
+//!
+//! Both hooks are optional and can be regular methods or `async def` coroutines, which are run on
+//! the event loop of the node and cancelled if they do not complete within [`HOOK_TIMEOUT`].
+//!
+//! A node with a `run` coroutine has it cancelled before its `on_stop` is called, and scheduled
+//! again after its `on_start`, see the [`run`](crate::run) module.
+
+use crate::run::{stop_run_task, RunTask};
+use crate::{PythonError, FINALIZE_TIMEOUT};
+use pyo3::prelude::*;
+use std::sync::Mutex;
+use std::time::Duration;
+
+pub(crate) const ON_START: &str = "on_start";
+pub(crate) const ON_STOP: &str = "on_stop";
+
+/// How long we wait for an `async def` hook to complete.
+pub(crate) const HOOK_TIMEOUT: Duration = FINALIZE_TIMEOUT;
+
+/// Calls the `hook` of `node`, if it has one, and runs the awaitable it returns, if any, on
+/// `event_loop` for at most `timeout`.
+pub(crate) fn call_hook(
+    py: Python,
+    asyncio: &PyAny,
+    event_loop: &PyAny,
+    node: &PyAny,
+    hook: &str,
+    timeout: Duration,
+) -> PyResult<()> {
+    if !node.hasattr(hook)? {
+        return Ok(());
+    }
+
+    let result = node.call_method0(hook)?;
+    if !py
+        .import("inspect")?
+        .call_method1("isawaitable", (result,))?
+        .is_true()?
+    {
+        return Ok(());
+    }
+
+    let bounded = asyncio.call_method1("wait_for", (result, timeout.as_secs_f64()))?;
+    // The event loop of a node with a `run` coroutine keeps running in its own thread.
+    if event_loop.call_method0("is_running")?.is_true()? {
+        asyncio
+            .call_method1("run_coroutine_threadsafe", (bounded, event_loop))?
+            .call_method0("result")?;
+    } else {
+        event_loop.call_method1("run_until_complete", (bounded,))?;
+    }
+
+    Ok(())
+}
+
+/// Stops the `run` coroutine of the node, if it is scheduled, then calls its `on_stop` hook when
+/// dropped.
+pub(crate) struct StopGuard<'a> {
+    pub(crate) asyncio: &'a PyObject,
+    pub(crate) event_loop: &'a PyObject,
+    pub(crate) node: &'a PyObject,
+    pub(crate) run_task: &'a Mutex<Option<RunTask>>,
+    pub(crate) node_id: Option<&'a str>,
+}
+
+impl Drop for StopGuard<'_> {
+    fn drop(&mut self) {
+        Python::with_gil(|py| {
+            let asyncio = self.asyncio.as_ref(py);
+            let event_loop = self.event_loop.as_ref(py);
+            stop_run_task(py, self.run_task, asyncio, event_loop);
+
+            let stopped = call_hook(
+                py,
+                asyncio,
+                event_loop,
+                self.node.as_ref(py),
+                ON_STOP,
+                HOOK_TIMEOUT,
+            );
+            if let Err(e) = stopped {
+                log::error!(
+                    "The {ON_STOP} of the Python node failed:\n{}",
+                    PythonError::new(py, &e).with_node_id(self.node_id)
+                );
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    #[test]
+    fn async_hooks_are_bounded_by_the_timeout() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let module = PyModule::from_code(
+                py,
+                "import asyncio\n\
+                 class Node:\n    async def on_start(self):\n        await asyncio.sleep(3600)\n",
+                "node.py",
+                "node",
+            )
+            .unwrap();
+            let node = module.getattr("Node").unwrap().call0().unwrap();
+            let asyncio = py.import("asyncio").unwrap();
+            let event_loop = asyncio.call_method0("new_event_loop").unwrap();
+
+            let start = Instant::now();
+            let err = call_hook(
+                py,
+                asyncio,
+                event_loop,
+                node,
+                ON_START,
+                Duration::from_millis(100),
+            )
+            .unwrap_err();
+            assert!(start.elapsed() < HOOK_TIMEOUT);
+            assert!(err.matches(py, asyncio.getattr("TimeoutError").unwrap()));
+
+            // A missing hook is not an error.
+            call_hook(py, asyncio, event_loop, node, ON_STOP, HOOK_TIMEOUT).unwrap();
+        });
+    }
+}
@@ -0,0 +1,381 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+//! Loading of the class of a Python node.
+//!
+//! By default the class is the one returned by the `register()` function of the Python script
+//! given in the `uri` of the descriptor. Two keys of the node configuration change this:
+//! - `python-module`, e.g. `mypkg.nodes.detector`, imports the node through the Python import
+//!   system instead of compiling the script, so that it can be part of an installed package and
+//!   use relative imports,
+//! - `class`, e.g. `Detector`, picks the class of the node in the module instead of calling
+//!   `register()`.
+//!
+//! The Python environment of the node is set with two other keys:
+//! - `python-venv`, the directory of a virtual environment, whose site-packages are prepended to
+//!   `sys.path` along with the directories listed in their `.pth` files; the creation of the node
+//!   fails if the virtual environment does not exist,
+//! - `python-path`, a directory or a list of directories, prepended to `sys.path` before the
+//!   site-packages of the virtual environment; the directories that do not exist are skipped.
+//!
+//! Relative paths are relative to the directory of the Python script. As all the Python nodes of
+//! a runtime share the same interpreter, `sys.path` and the modules already imported are shared as
+//! well: a node cannot import a different version of a module that another node imported.
+//!
+//! All these keys are removed from the configuration given to the Python node.
+
+use crate::PythonError;
+use pyo3::exceptions::PyFileNotFoundError;
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyList, PyModule};
+use std::path::Path;
+use zenoh_flow::prelude::{zferror, Configuration, ErrorKind, Result};
+
+const PYTHON_MODULE: &str = "python-module";
+const CLASS: &str = "class";
+const PYTHON_VENV: &str = "python-venv";
+const PYTHON_PATH: &str = "python-path";
+
+/// Loads the class of the Python node, from the `python-module` of the `configuration` or from
+/// the `script_file_path`, compiled as the module `module_name`.
+///
+/// The exceptions raised while loading the class are attributed to the node `node_id`.
+///
+/// # Errors
+///
+/// This function fails if a key is not a string, if the module cannot be loaded or if the class
+/// cannot be found in it.
+pub fn load_node_class<'p>(
+    py: Python<'p>,
+    script_file_path: &Path,
+    configuration: &mut Configuration,
+    module_name: &str,
+    node_id: Option<&str>,
+) -> Result<&'p PyAny> {
+    let zferr = |e: PyErr| PythonError::new(py, &e).with_node_id(node_id).into_zferr();
+
+    let (python_module, class, python_venv, python_path) = match configuration.as_object_mut() {
+        Some(configuration) => (
+            take_string(configuration, PYTHON_MODULE)?,
+            take_string(configuration, CLASS)?,
+            take_string(configuration, PYTHON_VENV)?,
+            take_strings(configuration, PYTHON_PATH)?,
+        ),
+        None => (None, None, None, Vec::new()),
+    };
+
+    let base_dir = script_file_path.parent().unwrap_or_else(|| Path::new("."));
+    prepend_sys_path(py, base_dir, python_venv, python_path).map_err(zferr)?;
+
+    let module = match python_module {
+        Some(python_module) => {
+            log::debug!("Importing the Python module {python_module}");
+            py.import(python_module.as_str()).map_err(zferr)?
+        }
+        None => {
+            let code = std::fs::read_to_string(script_file_path).map_err(|e| {
+                zferror!(
+                    ErrorKind::IOError,
+                    "Unable to read the Python script {}: {e}",
+                    script_file_path.display()
+                )
+            })?;
+            PyModule::from_code(py, &code, &script_file_path.to_string_lossy(), module_name)
+                .map_err(zferr)?
+        }
+    };
+
+    match class {
+        Some(class) => module.getattr(class.as_str()),
+        None => module.call_method0("register"),
+    }
+    .map_err(zferr)
+}
+
+/// Prepends to `sys.path` the `python_path` directories and the site-packages of the
+/// `python_venv`, relative to `base_dir`, and logs the resulting `sys.path`.
+///
+/// The site-packages are added with `site.addsitedir`, which processes their `.pth` files, e.g.
+/// the ones of the packages installed in editable mode.
+///
+/// # Errors
+///
+/// This function fails with a `FileNotFoundError` if the `python_venv` does not exist. The
+/// `python_path` directories that do not exist are skipped, with a warning.
+fn prepend_sys_path(
+    py: Python,
+    base_dir: &Path,
+    python_venv: Option<String>,
+    python_path: Vec<String>,
+) -> PyResult<()> {
+    let sys_path = py.import("sys")?.getattr("path")?.downcast::<PyList>()?;
+
+    let mut directories = Vec::with_capacity(python_path.len());
+    for directory in python_path {
+        let directory = base_dir.join(directory);
+        if directory.is_dir() {
+            directories.push(directory.to_string_lossy().into_owned());
+        } else {
+            log::warn!(
+                "The {PYTHON_PATH} directory {} does not exist, skipping it",
+                directory.display()
+            );
+        }
+    }
+
+    if let Some(python_venv) = python_venv {
+        let venv = base_dir.join(python_venv);
+        if !venv.is_dir() {
+            return Err(PyFileNotFoundError::new_err(format!(
+                "The {PYTHON_VENV} {} does not exist",
+                venv.display()
+            )));
+        }
+
+        let site_packages = venv_site_packages(py, &venv)?
+            .into_iter()
+            .filter(|site_packages| Path::new(site_packages).is_dir())
+            .collect::<Vec<_>>();
+        if site_packages.is_empty() {
+            return Err(PyFileNotFoundError::new_err(format!(
+                "The {PYTHON_VENV} {} has no site-packages",
+                venv.display()
+            )));
+        }
+
+        // `addsitedir` appends to `sys.path`: what it added is moved in front afterwards.
+        let before: Vec<String> = sys_path.extract()?;
+        let site = py.import("site")?;
+        for site_packages in &site_packages {
+            site.call_method1("addsitedir", (site_packages,))?;
+        }
+        let added = sys_path
+            .extract::<Vec<String>>()?
+            .into_iter()
+            .filter(|directory| !before.contains(directory));
+        for directory in site_packages.into_iter().chain(added) {
+            if !directories.contains(&directory) {
+                directories.push(directory);
+            }
+        }
+    }
+
+    for directory in directories.iter().rev() {
+        while sys_path.contains(directory)? {
+            sys_path.call_method1("remove", (directory,))?;
+        }
+        sys_path.insert(0, directory)?;
+    }
+
+    log::info!("Python sys.path: {}", sys_path);
+    Ok(())
+}
+
+/// Returns the site-packages directories of the virtual environment `venv`, as per `sysconfig`.
+fn venv_site_packages(py: Python, venv: &Path) -> PyResult<Vec<String>> {
+    let venv = venv.to_string_lossy();
+    let vars = PyDict::new(py);
+    vars.set_item("base", venv.as_ref())?;
+    vars.set_item("platbase", venv.as_ref())?;
+    let sysconfig = py.import("sysconfig")?;
+    let schemes: Vec<String> = sysconfig.call_method0("get_scheme_names")?.extract()?;
+    let scheme = if schemes.iter().any(|scheme| scheme == "venv") {
+        "venv"
+    } else if cfg!(target_family = "windows") {
+        "nt"
+    } else {
+        "posix_prefix"
+    };
+
+    let mut site_packages = Vec::with_capacity(2);
+    for name in ["purelib", "platlib"] {
+        let directory: String = sysconfig
+            .call_method1("get_path", (name, scheme, vars))?
+            .extract()?;
+        if !site_packages.contains(&directory) {
+            site_packages.push(directory);
+        }
+    }
+    Ok(site_packages)
+}
+
+pub(crate) fn take_string(
+    configuration: &mut serde_json::Map<String, Configuration>,
+    key: &str,
+) -> Result<Option<String>> {
+    match configuration.remove(key) {
+        Some(Configuration::String(value)) => Ok(Some(value)),
+        Some(value) => Err(zferror!(
+            ErrorKind::ConfigurationError,
+            "{key} must be a string, found {value}"
+        )
+        .into()),
+        None => Ok(None),
+    }
+}
+
+fn take_strings(
+    configuration: &mut serde_json::Map<String, Configuration>,
+    key: &str,
+) -> Result<Vec<String>> {
+    match configuration.remove(key) {
+        Some(Configuration::String(value)) => Ok(vec![value]),
+        Some(Configuration::Array(values)) => values
+            .into_iter()
+            .map(|value| match value {
+                Configuration::String(value) => Ok(value),
+                value => Err(zferror!(
+                    ErrorKind::ConfigurationError,
+                    "{key} must only contain strings, found {value}"
+                )
+                .into()),
+            })
+            .collect(),
+        Some(value) => Err(zferror!(
+            ErrorKind::ConfigurationError,
+            "{key} must be a string or a list of strings, found {value}"
+        )
+        .into()),
+        None => Ok(Vec::new()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn object(configuration: Configuration) -> serde_json::Map<String, Configuration> {
+        match configuration {
+            Configuration::Object(configuration) => configuration,
+            _ => unreachable!("the configuration is an object"),
+        }
+    }
+
+    #[test]
+    fn take_string_takes_strings_only() {
+        let mut configuration = object(json!({ "class": "Detector", "python-module": 3 }));
+        assert_eq!(
+            take_string(&mut configuration, CLASS).unwrap().as_deref(),
+            Some("Detector")
+        );
+        assert!(!configuration.contains_key(CLASS));
+        assert_eq!(take_string(&mut configuration, CLASS).unwrap(), None);
+
+        let err = take_string(&mut configuration, PYTHON_MODULE).unwrap_err();
+        assert!(err.to_string().contains("python-module must be a string"));
+    }
+
+    #[test]
+    fn take_strings_takes_a_string_or_a_list_of_strings() {
+        let mut configuration = object(json!({
+            "one": "lib",
+            "many": ["lib", "vendor"],
+            "mixed": ["lib", 3],
+            "number": 3,
+        }));
+        assert_eq!(take_strings(&mut configuration, "one").unwrap(), ["lib"]);
+        assert_eq!(
+            take_strings(&mut configuration, "many").unwrap(),
+            ["lib", "vendor"]
+        );
+        assert!(take_strings(&mut configuration, "missing")
+            .unwrap()
+            .is_empty());
+
+        let err = take_strings(&mut configuration, "mixed").unwrap_err();
+        assert!(err.to_string().contains("must only contain strings"));
+        let err = take_strings(&mut configuration, "number").unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("must be a string or a list of strings"));
+    }
+
+    #[test]
+    fn exceptions_are_attributed_to_the_node() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let script = std::env::temp_dir().join(format!("zf-node-{}.py", std::process::id()));
+            std::fs::write(&script, "raise ImportError('no detector')\n").unwrap();
+
+            let err = load_node_class(py, &script, &mut Configuration::Null, "op", Some("det"))
+                .unwrap_err();
+            std::fs::remove_file(script).unwrap();
+            assert!(
+                err.to_string()
+                    .contains("ImportError: no detector (node det)"),
+                "{}",
+                err
+            );
+        });
+    }
+
+    #[test]
+    fn missing_scripts_are_errors() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let script = std::env::temp_dir().join(format!("zf-missing-{}.py", std::process::id()));
+
+            let err =
+                load_node_class(py, &script, &mut Configuration::Null, "op", None).unwrap_err();
+            assert!(
+                err.to_string().contains("Unable to read the Python script"),
+                "{}",
+                err
+            );
+        });
+    }
+
+    #[test]
+    fn virtual_environments_are_added_as_site_directories() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let sys_path = py.import("sys").unwrap().getattr("path").unwrap();
+            let saved: Vec<String> = sys_path.extract().unwrap();
+
+            let base_dir = std::env::temp_dir().join(format!("zf-loader-{}", std::process::id()));
+            let venv = base_dir.join("venv");
+            let site_packages = venv_site_packages(py, &venv).unwrap().remove(0);
+            let editable = base_dir.join("editable");
+            std::fs::create_dir_all(&site_packages).unwrap();
+            std::fs::create_dir_all(&editable).unwrap();
+            std::fs::write(
+                Path::new(&site_packages).join("editable.pth"),
+                editable.to_string_lossy().as_bytes(),
+            )
+            .unwrap();
+
+            prepend_sys_path(
+                py,
+                &base_dir,
+                Some("venv".to_string()),
+                vec!["missing".to_string()],
+            )
+            .unwrap();
+            let path: Vec<String> = sys_path.extract().unwrap();
+            assert_eq!(
+                path[..2],
+                [site_packages, editable.to_string_lossy().into_owned()]
+            );
+
+            let err = prepend_sys_path(py, &base_dir, Some("missing".to_string()), Vec::new())
+                .unwrap_err();
+            assert!(err.is_instance_of::<PyFileNotFoundError>(py));
+
+            sys_path.call_method1("clear", ()).unwrap();
+            sys_path.call_method1("extend", (saved,)).unwrap();
+            std::fs::remove_dir_all(base_dir).unwrap();
+        });
+    }
+}
@@ -0,0 +1,283 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+//! Encoding of NumPy arrays exchanged between nodes.
+//!
+//! An encoded array is a header followed by the raw, C-contiguous, data of the array. All the
+//! fields of the header are little-endian:
+//!
+//! | field   | size            | description                                  |
+//! |---------|-----------------|----------------------------------------------|
+//! | magic   | 4               | `b"ZFND"`                                    |
+//! | version | 1               | version of the encoding, currently `1`       |
+//! | dtype   | 1 + len         | length and ASCII `dtype.str`, e.g. `"<f4"`   |
+//! | ndim    | 1               | number of dimensions                         |
+//! | shape   | 8 × ndim        | `u64` dimensions                             |
+//! | strides | 8 × ndim        | `i64` strides, in bytes                      |
+//! | padding | up to 15        | zeroes, so that the data is 16-bytes aligned |
+//!
+//! The dtype carries the byte order explicitly (`<`, `>` or `|`), the receiver therefore always
+//! interprets the data as it was produced, whatever the architecture of both hosts.
+
+use crate::buffer::{BufferInner, PyPayload};
+use pyo3::exceptions::{PyImportError, PyTypeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use std::convert::TryInto;
+use std::sync::Arc;
+use zenoh_flow::prelude::{zferror, ErrorKind, SendSyncAny};
+use zenoh_flow::types::Payload;
+
+const MAGIC: &[u8; 4] = b"ZFND";
+const VERSION: u8 = 1;
+const ALIGNMENT: usize = 16;
+
+/// The description of an encoded NumPy array.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Header {
+    dtype: String,
+    shape: Vec<u64>,
+    strides: Vec<i64>,
+}
+
+impl Header {
+    fn encode(&self) -> Vec<u8> {
+        let mut buffer = Vec::with_capacity(
+            MAGIC.len() + 3 + self.dtype.len() + 16 * self.shape.len() + ALIGNMENT,
+        );
+        buffer.extend_from_slice(MAGIC);
+        buffer.push(VERSION);
+        buffer.push(self.dtype.len() as u8);
+        buffer.extend_from_slice(self.dtype.as_bytes());
+        buffer.push(self.shape.len() as u8);
+        self.shape
+            .iter()
+            .for_each(|dim| buffer.extend_from_slice(&dim.to_le_bytes()));
+        self.strides
+            .iter()
+            .for_each(|stride| buffer.extend_from_slice(&stride.to_le_bytes()));
+        buffer.resize(padded(buffer.len()), 0);
+
+        buffer
+    }
+
+    /// Decodes the header at the beginning of `bytes`, returning it with the offset of the data.
+    fn decode(bytes: &[u8]) -> PyResult<(Self, usize)> {
+        let mut cursor = Cursor { bytes, offset: 0 };
+
+        if cursor.take(MAGIC.len())? != MAGIC {
+            return Err(PyValueError::new_err(
+                "The data received is not an encoded NumPy array",
+            ));
+        }
+
+        let version = cursor.take(1)?[0];
+        if version != VERSION {
+            return Err(PyValueError::new_err(format!(
+                "Unsupported NumPy array encoding version {version}, expected {VERSION}"
+            )));
+        }
+
+        let dtype_len = cursor.take(1)?[0] as usize;
+        let dtype = std::str::from_utf8(cursor.take(dtype_len)?)
+            .map_err(|e| PyValueError::new_err(format!("Invalid NumPy dtype: {e}")))?
+            .to_string();
+
+        let ndim = cursor.take(1)?[0] as usize;
+        let shape = (0..ndim)
+            .map(|_| cursor.take_8().map(u64::from_le_bytes))
+            .collect::<PyResult<Vec<_>>>()?;
+        let strides = (0..ndim)
+            .map(|_| cursor.take_8().map(i64::from_le_bytes))
+            .collect::<PyResult<Vec<_>>>()?;
+
+        let offset = padded(cursor.offset);
+        if offset > bytes.len() {
+            return Err(truncated());
+        }
+
+        Ok((
+            Self {
+                dtype,
+                shape,
+                strides,
+            },
+            offset,
+        ))
+    }
+
+    /// Builds a read-only array over `buffer`, starting at `offset`.
+    fn into_ndarray(self, py: Python, buffer: PyObject, offset: usize) -> PyResult<PyObject> {
+        let numpy = import_numpy(py)?;
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("shape", self.shape)?;
+        kwargs.set_item("dtype", self.dtype)?;
+        kwargs.set_item("buffer", buffer)?;
+        kwargs.set_item("offset", offset)?;
+        kwargs.set_item("strides", self.strides)?;
+
+        Ok(numpy.getattr("ndarray")?.call((), Some(kwargs))?.into())
+    }
+}
+
+struct Cursor<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn take(&mut self, len: usize) -> PyResult<&'a [u8]> {
+        let slice = self
+            .bytes
+            .get(self.offset..self.offset + len)
+            .ok_or_else(truncated)?;
+        self.offset += len;
+        Ok(slice)
+    }
+
+    fn take_8(&mut self) -> PyResult<[u8; 8]> {
+        Ok(self.take(8)?.try_into().expect("slice has 8 bytes"))
+    }
+}
+
+fn padded(len: usize) -> usize {
+    (len + ALIGNMENT - 1) / ALIGNMENT * ALIGNMENT
+}
+
+fn truncated() -> PyErr {
+    PyValueError::new_err("The encoded NumPy array is truncated")
+}
+
+fn import_numpy(py: Python) -> PyResult<&PyModule> {
+    py.import("numpy").map_err(|e| {
+        PyImportError::new_err(format!(
+            "NumPy ports require the `numpy` package to be installed: {e}"
+        ))
+    })
+}
+
+/// An array sent to a node running in the same process: its data is shared, not copied.
+struct NdArrayPayload {
+    header: Header,
+    data: Arc<PyPayload>,
+}
+
+/// Encodes `array`, or any object NumPy can convert to an array, into a [`Payload`](`Payload`).
+///
+/// The data of the array is copied only if the array is not C-contiguous or, as any other
+/// Python payload, if it has to be serialized for a node running in another process.
+pub(crate) fn encode(array: &PyAny) -> PyResult<Payload> {
+    let numpy = import_numpy(array.py())?;
+    let array = numpy.call_method1("require", (array, array.py().None(), "C"))?;
+
+    let dtype = array.getattr("dtype")?;
+    if dtype.getattr("hasobject")?.is_true()? || !dtype.getattr("fields")?.is_none() {
+        return Err(PyTypeError::new_err(format!(
+            "Unsupported dtype {dtype}: only arrays of numbers, booleans, strings or bytes can be sent"
+        )));
+    }
+
+    let header = Header {
+        dtype: dtype.getattr("str")?.extract()?,
+        shape: array.getattr("shape")?.extract()?,
+        strides: array.getattr("strides")?.extract()?,
+    };
+    let data = Arc::new(PyPayload::new(array)?);
+
+    Ok(Payload::Typed((
+        Arc::new(NdArrayPayload { header, data }) as Arc<dyn SendSyncAny>,
+        Arc::new(|buffer: &mut Vec<u8>, payload: Arc<dyn SendSyncAny>| {
+            let payload = (*payload)
+                .as_any()
+                .downcast_ref::<NdArrayPayload>()
+                .ok_or_else(|| {
+                    zferror!(
+                        ErrorKind::SerializationError,
+                        "Payload is not a NumPy array"
+                    )
+                })?;
+            buffer.extend_from_slice(&payload.header.encode());
+            buffer.extend_from_slice((*payload.data).as_ref());
+            Ok(())
+        }),
+    )))
+}
+
+/// Decodes the array contained in the [`Payload`](`Payload`).
+///
+/// The returned array is read-only and shares its memory with the received payload.
+pub(crate) fn decode(py: Python, payload: &Payload) -> PyResult<PyObject> {
+    if let Payload::Typed((data, _)) = payload {
+        if let Some(array) = (**data).as_any().downcast_ref::<NdArrayPayload>() {
+            let buffer = BufferInner::Python(array.data.clone()).into_memoryview(py)?;
+            return array.header.clone().into_ndarray(py, buffer, 0);
+        }
+    }
+
+    let bytes = payload
+        .try_as_bytes()
+        .map_err(|e| PyValueError::new_err(format!("try_as_bytes field: {e}")))?;
+    let (header, offset) = Header::decode(&bytes)?;
+    let buffer = BufferInner::Rust(bytes).into_memoryview(py)?;
+    header.into_ndarray(py, buffer, offset)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header() -> Header {
+        Header {
+            dtype: "<f8".to_string(),
+            shape: vec![2, 3],
+            strides: vec![24, 8],
+        }
+    }
+
+    fn decode_error(bytes: &[u8]) -> String {
+        Header::decode(bytes).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn header_round_trip() {
+        pyo3::prepare_freethreaded_python();
+        let encoded = header().encode();
+        assert_eq!(encoded.len() % ALIGNMENT, 0);
+
+        let (decoded, offset) = Header::decode(&encoded).unwrap();
+        assert_eq!(decoded, header());
+        assert_eq!(offset, encoded.len());
+    }
+
+    #[test]
+    fn invalid_headers_are_rejected() {
+        pyo3::prepare_freethreaded_python();
+        let encoded = header().encode();
+
+        let mut bad_magic = encoded.clone();
+        bad_magic[0] = b'X';
+        assert!(decode_error(&bad_magic).contains("not an encoded NumPy array"));
+
+        let mut bad_version = encoded.clone();
+        bad_version[MAGIC.len()] = VERSION + 1;
+        assert!(decode_error(&bad_version).contains("Unsupported NumPy array encoding version"));
+
+        // The number of dimensions follows the magic, the version and the dtype.
+        let mut bad_ndim = encoded.clone();
+        bad_ndim[MAGIC.len() + 2 + header().dtype.len()] = u8::MAX;
+        assert!(decode_error(&bad_ndim).contains("truncated"));
+
+        assert!(decode_error(&encoded[..MAGIC.len() + 3]).contains("truncated"));
+    }
+}
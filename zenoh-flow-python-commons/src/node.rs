@@ -0,0 +1,345 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+//! The bootstrap of a Python node, shared by the source, sink and operator wrappers.
+//!
+//! A wrapper only declares its kind of node, with its ports, and forwards the `iteration` of
+//! Zenoh Flow to the [`PythonNode`]: loading the class of the node, its configuration and its
+//! asyncio event loop is done here, for all kinds of nodes. The Python library is expected to be
+//! loaded already, with its symbols made global.
+
+use crate::error::take_node_id;
+use crate::finalize::take_finalize_timeout;
+use crate::{
+    check_ports_taken, configuration_into_py, configuration_schema, context_into_py,
+    inputs_into_py, load_node_class, outputs_into_py, validate_configuration, PortTypes,
+    PythonError, PythonState,
+};
+use pyo3::prelude::*;
+use pyo3::types::PyTuple;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use zenoh_flow::bail;
+use zenoh_flow::prelude::{Configuration, Context, ErrorKind, Inputs, Outputs, Result};
+
+const PYTHON_SCRIPT: &str = "python-script";
+const CONFIGURATION: &str = "configuration";
+
+/// The kind of a Python node, with the ports it is given.
+pub enum NodeKind {
+    Source(Outputs),
+    Sink(Inputs),
+    Operator(Inputs, Outputs),
+}
+
+impl NodeKind {
+    /// The name of the module in which the Python script of the node is compiled.
+    fn module_name(&self) -> &'static str {
+        match self {
+            NodeKind::Source(_) => "source",
+            NodeKind::Sink(_) => "sink",
+            NodeKind::Operator(_, _) => "op",
+        }
+    }
+
+    /// The ids of the ports of the node.
+    fn port_ids(&self) -> Vec<&str> {
+        match self {
+            NodeKind::Source(outputs) => outputs.keys().map(|id| id.as_ref()).collect(),
+            NodeKind::Sink(inputs) => inputs.keys().map(|id| id.as_ref()).collect(),
+            NodeKind::Operator(inputs, outputs) => inputs
+                .keys()
+                .chain(outputs.keys())
+                .map(|id| id.as_ref())
+                .collect(),
+        }
+    }
+}
+
+/// A Python node, of any kind.
+#[derive(Clone, Debug)]
+pub struct PythonNode {
+    state: Arc<PythonState>,
+}
+
+impl PythonNode {
+    /// Creates the Python node of `kind`, described by the `configuration` given by the loader.
+    ///
+    /// # Errors
+    ///
+    /// This function fails if the configuration is invalid or if the Python node cannot be
+    /// created.
+    pub fn new(
+        kind: NodeKind,
+        context: Context,
+        configuration: Option<Configuration>,
+    ) -> Result<Self> {
+        pyo3::prepare_freethreaded_python();
+
+        let state = Python::with_gil(|py| create_state(py, kind, &context, configuration))?;
+
+        Ok(Self {
+            state: Arc::new(state),
+        })
+    }
+
+    /// Runs one iteration of the Python node.
+    pub async fn iteration(&self) -> Result<()> {
+        self.state.iteration().await
+    }
+}
+
+/// Creates the Python node of `kind` and its event loop.
+fn create_state(
+    py: Python,
+    kind: NodeKind,
+    context: &Context,
+    configuration: Option<Configuration>,
+) -> Result<PythonState> {
+    let module_name = kind.module_name();
+    let (script_file_path, mut py_config) = split_configuration(module_name, configuration)?;
+    let node_id = take_node_id(&mut py_config)?;
+    let finalize_timeout = take_finalize_timeout(&mut py_config)?;
+    let zferr = |e: PyErr| {
+        PythonError::new(py, &e)
+            .with_node_id(node_id.as_deref())
+            .into_zferr()
+    };
+    let port_types = PortTypes::from_configuration(
+        &mut py_config,
+        script_file_path.parent().unwrap_or_else(|| Path::new(".")),
+    )?;
+    port_types.check_ports(&kind.port_ids())?;
+
+    // Load the Python class of the node
+    let class = load_node_class(
+        py,
+        &script_file_path,
+        &mut py_config,
+        module_name,
+        node_id.as_deref(),
+    )?;
+
+    // Validate the configuration against the schema of the node, if any
+    if let Some(schema) = configuration_schema(class).map_err(zferr)? {
+        validate_configuration(&schema, &py_config)?;
+    }
+
+    let py_config = configuration_into_py(py, py_config).map_err(zferr)?;
+    let (py_receivers, py_senders) = ports_into_py(py, kind, &port_types).map_err(zferr)?;
+    let asyncio = py.import("asyncio").map_err(zferr)?;
+    let event_loop = new_event_loop(asyncio).map_err(zferr)?;
+    let py_ctx = context_into_py(&py, context).map_err(zferr)?;
+
+    let py_node = instantiate(
+        class,
+        py_ctx,
+        py_config,
+        py_receivers.as_ref(),
+        py_senders.as_ref(),
+    )
+    .map_err(zferr)?;
+    check_ports_taken(
+        py,
+        py_receivers.as_ref(),
+        py_senders.as_ref(),
+        node_id.as_deref(),
+    )?;
+    let has_run = crate::run::has_run(py, py_node.as_ref(py)).map_err(zferr)?;
+
+    log::debug!(
+        "Created the Python {module_name} {}",
+        script_file_path.display()
+    );
+
+    Ok(PythonState {
+        module: Arc::new(class.into()),
+        py_state: Arc::new(py_node),
+        event_loop: Arc::new(event_loop.into()),
+        asyncio_module: Arc::new(asyncio.into()),
+        run_task: Default::default(),
+        has_run,
+        finalize_timeout,
+        node_id,
+    })
+}
+
+/// Splits the `configuration` given by the loader into the path of the Python script and the
+/// configuration of the Python node.
+///
+/// # Errors
+///
+/// This function fails if there is no configuration, if it is not a map or if it has no
+/// `python-script`.
+fn split_configuration(
+    module_name: &str,
+    configuration: Option<Configuration>,
+) -> Result<(PathBuf, Configuration)> {
+    let mut configuration = match configuration {
+        Some(Configuration::Object(configuration)) => configuration,
+        Some(configuration) => bail!(
+            ErrorKind::ConfigurationError,
+            "The configuration of the Python {module_name} must be a map, found {configuration}"
+        ),
+        None => bail!(
+            ErrorKind::InvalidState,
+            "The Python {module_name} has no configuration"
+        ),
+    };
+
+    let script_file_path = match configuration.remove(PYTHON_SCRIPT) {
+        Some(Configuration::String(script_file_path)) => PathBuf::from(script_file_path),
+        _ => bail!(
+            ErrorKind::InvalidState,
+            "The configuration of the Python {module_name} has no {PYTHON_SCRIPT}"
+        ),
+    };
+
+    let py_config = configuration
+        .remove(CONFIGURATION)
+        .unwrap_or(Configuration::Null);
+    Ok((script_file_path, py_config))
+}
+
+/// Converts the ports of the node, returning its inputs and outputs, depending on its `kind`.
+fn ports_into_py(
+    py: Python,
+    kind: NodeKind,
+    port_types: &PortTypes,
+) -> PyResult<(Option<PyObject>, Option<PyObject>)> {
+    Ok(match kind {
+        NodeKind::Source(outputs) => (None, Some(outputs_into_py(py, outputs, port_types)?)),
+        NodeKind::Sink(inputs) => (Some(inputs_into_py(py, inputs, port_types)?), None),
+        NodeKind::Operator(inputs, outputs) => (
+            Some(inputs_into_py(py, inputs, port_types)?),
+            Some(outputs_into_py(py, outputs, port_types)?),
+        ),
+    })
+}
+
+/// Creates a new event loop and sets it as the event loop of the current thread.
+fn new_event_loop(asyncio: &PyAny) -> PyResult<&PyAny> {
+    let event_loop = asyncio.call_method0("new_event_loop")?;
+    asyncio.call_method1("set_event_loop", (event_loop,))?;
+    Ok(event_loop)
+}
+
+/// Instantiates the `class` of the node: its ports follow its context and configuration.
+fn instantiate(
+    class: &PyAny,
+    py_ctx: &PyAny,
+    py_config: PyObject,
+    py_receivers: Option<&PyObject>,
+    py_senders: Option<&PyObject>,
+) -> PyResult<PyObject> {
+    let py = class.py();
+    let mut args = vec![py_ctx.to_object(py), py_config];
+    args.extend(py_receivers.map(|receivers| receivers.clone_ref(py)));
+    args.extend(py_senders.map(|senders| senders.clone_ref(py)));
+    Ok(class.call1(PyTuple::new(py, args))?.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pyo3::types::PyDict;
+
+    #[test]
+    fn invalid_configurations_are_errors() {
+        let script = serde_json::json!({ "python-script": "node.py" });
+        let (script_file_path, py_config) = split_configuration("op", Some(script)).unwrap();
+        assert_eq!(script_file_path, Path::new("node.py"));
+        assert_eq!(py_config, Configuration::Null);
+
+        for configuration in [
+            None,
+            Some(serde_json::json!(["python-script", "node.py"])),
+            Some(serde_json::json!({ "configuration": {} })),
+            Some(serde_json::json!({ "python-script": 3 })),
+        ] {
+            assert!(split_configuration("op", configuration).is_err());
+        }
+    }
+
+    #[test]
+    fn asyncio_without_new_event_loop_is_an_error() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let asyncio = py
+                .import("types")
+                .unwrap()
+                .call_method1("ModuleType", ("asyncio",))
+                .unwrap();
+            assert!(new_event_loop(asyncio).is_err());
+
+            let asyncio = py.import("asyncio").unwrap();
+            let event_loop = new_event_loop(asyncio).unwrap();
+            event_loop.call_method0("close").unwrap();
+        });
+    }
+
+    #[test]
+    fn failing_set_event_loop_is_an_error() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let asyncio = PyModule::from_code(
+                py,
+                "def new_event_loop():\n    return object()\n\ndef set_event_loop(event_loop):\n    raise RuntimeError('no event loop policy')\n",
+                "asyncio.py",
+                "fake_asyncio",
+            )
+            .unwrap();
+
+            let err = new_event_loop(asyncio).unwrap_err();
+            let err = PythonError::new(py, &err).into_zferr();
+            assert!(
+                err.to_string()
+                    .contains("RuntimeError: no event loop policy"),
+                "{}",
+                err
+            );
+        });
+    }
+
+    #[test]
+    fn raising_init_is_an_error() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let module = PyModule::from_code(
+                py,
+                "class Node:\n    def __init__(self, context, configuration, inputs, outputs):\n        raise ValueError('invalid threshold')\n",
+                "node.py",
+                "node",
+            )
+            .unwrap();
+            let class = module.getattr("Node").unwrap();
+            let ports = PyDict::new(py).to_object(py);
+
+            let err = instantiate(
+                class,
+                py.None().into_ref(py),
+                py.None(),
+                Some(&ports),
+                Some(&ports),
+            )
+            .unwrap_err();
+            let err = PythonError::new(py, &err).into_zferr();
+            assert!(
+                err.to_string().contains("ValueError: invalid threshold"),
+                "{}",
+                err
+            );
+        });
+    }
+}
@@ -0,0 +1,288 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+//! The types declared for the ports of a Python node.
+//!
+//! Zenoh Flow does not give the `type` of the ports, written in the descriptor, to the node, which
+//! is thus not used: the Python wrappers read the types from the `port-types` key of the node
+//! configuration, along with the descriptor set against which the Protobuf types are resolved:
+//!
+//! ```yaml
+//! inputs:
+//!   - id: Reading
+//!     type: protobuf:sensors.Reading
+//! configuration:
+//!   protobuf-descriptor-set: ./sensors.binpb
+//!   port-types:
+//!     Reading: protobuf:sensors.Reading
+//! ```
+//!
+//! The path of the descriptor set is relative to the directory of the Python script. Both keys
+//! are removed from the configuration given to the Python node.
+//!
+//! The type of every port of the node must be declared: the creation of the node fails if a port
+//! has no declared type, or if a type is declared for a port the node does not have. The declared
+//! types are checked, in Python, when the node takes its ports: a mismatch also fails the creation
+//! of the node.
+
+use crate::protobuf::ProtobufCodec;
+use prost_reflect::DescriptorPool;
+use pyo3::prelude::*;
+use std::collections::HashMap;
+use std::path::Path;
+use zenoh_flow::prelude::{zferror, Configuration, ErrorKind, Result};
+
+const PORT_TYPES: &str = "port-types";
+const PROTOBUF_DESCRIPTOR_SET: &str = "protobuf-descriptor-set";
+const PROTOBUF_PREFIX: &str = "protobuf:";
+
+/// The type declared for a port.
+#[pyclass]
+#[derive(Clone, Debug)]
+pub struct PortType {
+    name: String,
+    codec: Option<ProtobufCodec>,
+}
+
+#[pymethods]
+impl PortType {
+    /// The type, as written in the configuration, e.g. `protobuf:package.Message`.
+    #[getter]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The codec of the Protobuf message, if the port has a Protobuf type.
+    #[getter]
+    pub fn codec(&self) -> Option<ProtobufCodec> {
+        self.codec.clone()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("PortType({:?})", self.name)
+    }
+}
+
+/// The types declared for the ports of a node, indexed by port id.
+#[derive(Clone, Debug, Default)]
+pub struct PortTypes {
+    types: HashMap<String, PortType>,
+}
+
+impl PortTypes {
+    /// Takes the `port-types` and `protobuf-descriptor-set` keys out of the `configuration` and
+    /// resolves the Protobuf types against the descriptor set, relative to `base_dir`.
+    ///
+    /// # Errors
+    ///
+    /// This function fails if a key is malformed, if the descriptor set cannot be read or if a
+    /// Protobuf type is not in the descriptor set.
+    pub fn from_configuration(configuration: &mut Configuration, base_dir: &Path) -> Result<Self> {
+        let (types, descriptor_set) = match configuration.as_object_mut() {
+            Some(configuration) => (
+                configuration.remove(PORT_TYPES),
+                configuration.remove(PROTOBUF_DESCRIPTOR_SET),
+            ),
+            None => return Ok(Self::default()),
+        };
+
+        let pool = match descriptor_set {
+            Some(path) => {
+                let path = base_dir.join(path.as_str().ok_or_else(|| {
+                    zferror!(
+                        ErrorKind::ConfigurationError,
+                        "{PROTOBUF_DESCRIPTOR_SET} must be a path"
+                    )
+                })?);
+                let bytes = std::fs::read(&path).map_err(|e| {
+                    zferror!(
+                        ErrorKind::IOError,
+                        "Unable to read the descriptor set {}: {e}",
+                        path.display()
+                    )
+                })?;
+                let pool = DescriptorPool::decode(bytes.as_slice()).map_err(|e| {
+                    zferror!(
+                        ErrorKind::ConfigurationError,
+                        "Unable to decode the descriptor set {}: {e}",
+                        path.display()
+                    )
+                })?;
+                Some(pool)
+            }
+            None => None,
+        };
+
+        let types = match types {
+            Some(Configuration::Object(types)) => types,
+            Some(_) => {
+                return Err(zferror!(
+                    ErrorKind::ConfigurationError,
+                    "{PORT_TYPES} must map port ids to types"
+                )
+                .into())
+            }
+            None => return Ok(Self::default()),
+        };
+
+        let mut port_types = HashMap::with_capacity(types.len());
+        for (port_id, name) in types {
+            let name = name.as_str().ok_or_else(|| {
+                zferror!(
+                    ErrorKind::ConfigurationError,
+                    "The type of port {port_id} must be a string"
+                )
+            })?;
+
+            let codec = match name.strip_prefix(PROTOBUF_PREFIX) {
+                Some(message_name) => {
+                    let pool = pool.as_ref().ok_or_else(|| {
+                        zferror!(
+                            ErrorKind::ConfigurationError,
+                            "Port {port_id} has the type {name} but no {PROTOBUF_DESCRIPTOR_SET} is configured"
+                        )
+                    })?;
+                    let descriptor = pool.get_message_by_name(message_name).ok_or_else(|| {
+                        zferror!(
+                            ErrorKind::ConfigurationError,
+                            "Port {port_id}: message {message_name} not found in the descriptor set"
+                        )
+                    })?;
+                    Some(ProtobufCodec::new(descriptor))
+                }
+                None => None,
+            };
+
+            port_types.insert(
+                port_id,
+                PortType {
+                    name: name.to_string(),
+                    codec,
+                },
+            );
+        }
+
+        Ok(Self { types: port_types })
+    }
+
+    /// Returns the type declared for `port_id`, if any.
+    pub fn get(&self, port_id: &str) -> Option<&PortType> {
+        self.types.get(port_id)
+    }
+
+    /// Checks that types are declared for all the `port_ids`, the ports of the node, and for them
+    /// only.
+    ///
+    /// # Errors
+    ///
+    /// This function fails, naming them, if ports have no declared type or if types are declared
+    /// for ports the node does not have.
+    pub fn check_ports(&self, port_ids: &[&str]) -> Result<()> {
+        let mut undeclared = port_ids
+            .iter()
+            .filter(|port_id| !self.types.contains_key(**port_id))
+            .copied()
+            .collect::<Vec<_>>();
+        if !undeclared.is_empty() {
+            undeclared.sort_unstable();
+            undeclared.dedup();
+            return Err(zferror!(
+                ErrorKind::ConfigurationError,
+                "{PORT_TYPES} must declare the types of all the ports, missing: {}",
+                undeclared.join(", ")
+            )
+            .into());
+        }
+
+        let mut unknown = self
+            .types
+            .keys()
+            .filter(|port_id| !port_ids.contains(&port_id.as_str()))
+            .map(String::as_str)
+            .collect::<Vec<_>>();
+        if unknown.is_empty() {
+            return Ok(());
+        }
+
+        unknown.sort_unstable();
+        Err(zferror!(
+            ErrorKind::ConfigurationError,
+            "{PORT_TYPES} declares the types of unknown ports: {}",
+            unknown.join(", ")
+        )
+        .into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn types_of_unknown_ports_are_rejected() {
+        let mut configuration: Configuration = serde_json::json!({
+            "port-types": { "In": "ndarray", "Typo": "arrow", "Out": "usize" },
+            "threshold": 3,
+        });
+        let port_types = PortTypes::from_configuration(&mut configuration, Path::new(".")).unwrap();
+        assert_eq!(configuration, serde_json::json!({ "threshold": 3 }));
+        assert_eq!(port_types.get("In").unwrap().name(), "ndarray");
+
+        assert!(port_types.check_ports(&["In", "Typo", "Out"]).is_ok());
+        let err = port_types.check_ports(&["In", "Out"]).unwrap_err();
+        assert!(err.to_string().contains("unknown ports: Typo"), "{}", err);
+    }
+
+    #[test]
+    fn ports_without_a_type_are_rejected() {
+        let mut configuration: Configuration = serde_json::json!({
+            "port-types": { "In": "ndarray" },
+        });
+        let port_types = PortTypes::from_configuration(&mut configuration, Path::new(".")).unwrap();
+        let err = port_types.check_ports(&["In", "Out"]).unwrap_err();
+        assert!(err.to_string().contains("missing: Out"), "{}", err);
+
+        let port_types =
+            PortTypes::from_configuration(&mut Configuration::Null, Path::new(".")).unwrap();
+        assert!(port_types.check_ports(&[]).is_ok());
+        let err = port_types.check_ports(&["In"]).unwrap_err();
+        assert!(err.to_string().contains("missing: In"), "{}", err);
+    }
+
+    #[test]
+    fn integer_ports_reject_bool() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let check_port_type = crate::tests::python_types(py)
+                .getattr("_check_port_type")
+                .unwrap();
+            let port_type = Py::new(
+                py,
+                PortType {
+                    name: "usize".to_string(),
+                    codec: None,
+                },
+            )
+            .unwrap();
+
+            let builtins = py.import("builtins").unwrap();
+            for (data_type, matches) in [("int", true), ("bool", false), ("float", false)] {
+                let data_type = builtins.getattr(data_type).unwrap();
+                let res =
+                    check_port_type.call1(("In", port_type.clone_ref(py), "typed", data_type));
+                assert_eq!(res.is_ok(), matches, "{}", data_type);
+            }
+        });
+    }
+}
@@ -0,0 +1,232 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+//! Protobuf messages, described by a descriptor set, exchanged as Python dictionaries.
+//!
+//! The Protobuf type of a port is the one declared under `port-types` in the configuration of the
+//! node, see [`crate::PortTypes`]: the type written in the descriptor is not used.
+//!
+//! A message is decoded into a `dict` holding all its fields, including the ones set to their
+//! default value. Fields with explicit presence (`optional`, `oneof` and message fields) that are
+//! not set are `None`. Enumerations are decoded as their number and can be encoded from either
+//! their number or their name.
+
+use crate::buffer::{BufferInner, PyPayload};
+use prost_reflect::prost::bytes::Bytes;
+use prost_reflect::prost::Message;
+use prost_reflect::{
+    DynamicMessage, FieldDescriptor, Kind, MapKey, MessageDescriptor, ReflectMessage, Value,
+};
+use pyo3::exceptions::{PyTypeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyDict, PyList, PyString};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Encodes and decodes the Protobuf message `message_name`.
+///
+/// Messages are Python dictionaries, mapping the name of each field to its value: no message class
+/// is generated, see the module documentation for the representation of the fields.
+#[pyclass]
+#[derive(Clone, Debug)]
+pub struct ProtobufCodec {
+    descriptor: MessageDescriptor,
+}
+
+impl ProtobufCodec {
+    pub(crate) fn new(descriptor: MessageDescriptor) -> Self {
+        Self { descriptor }
+    }
+}
+
+#[pymethods]
+impl ProtobufCodec {
+    /// The fully qualified name of the message, e.g. `package.Message`.
+    #[getter]
+    pub fn message_name(&self) -> &str {
+        self.descriptor.full_name()
+    }
+
+    /// Encodes the dictionary `obj`, returning a read-only `memoryview` over the encoded bytes.
+    pub fn encode(&self, py: Python, obj: &PyAny) -> PyResult<PyObject> {
+        let message = message_from_py(&self.descriptor, obj)?;
+        BufferInner::Rust(Arc::new(message.encode_to_vec())).into_memoryview(py)
+    }
+
+    /// Decodes `data`, any object implementing the buffer protocol, into a dictionary.
+    pub fn decode(&self, py: Python, data: &PyAny) -> PyResult<PyObject> {
+        let payload = PyPayload::new(data)?;
+        let message =
+            DynamicMessage::decode(self.descriptor.clone(), payload.as_ref()).map_err(|e| {
+                PyValueError::new_err(format!(
+                    "Unable to decode {}: {e}",
+                    self.descriptor.full_name()
+                ))
+            })?;
+
+        message_into_py(py, &message)
+    }
+
+    fn __repr__(&self) -> String {
+        format!("ProtobufCodec({:?})", self.descriptor.full_name())
+    }
+}
+
+fn message_into_py(py: Python, message: &DynamicMessage) -> PyResult<PyObject> {
+    let dict = PyDict::new(py);
+    for field in message.descriptor().fields() {
+        if field.supports_presence() && !message.has_field(&field) {
+            dict.set_item(field.name(), py.None())?;
+        } else {
+            dict.set_item(field.name(), value_into_py(py, &message.get_field(&field))?)?;
+        }
+    }
+
+    Ok(dict.into())
+}
+
+fn value_into_py(py: Python, value: &Value) -> PyResult<PyObject> {
+    Ok(match value {
+        Value::Bool(b) => b.to_object(py),
+        Value::I32(i) | Value::EnumNumber(i) => i.to_object(py),
+        Value::I64(i) => i.to_object(py),
+        Value::U32(u) => u.to_object(py),
+        Value::U64(u) => u.to_object(py),
+        Value::F32(f) => f.to_object(py),
+        Value::F64(f) => f.to_object(py),
+        Value::String(s) => s.to_object(py),
+        Value::Bytes(b) => PyBytes::new(py, b).into(),
+        Value::Message(m) => message_into_py(py, m)?,
+        Value::List(values) => {
+            let list = PyList::empty(py);
+            for value in values {
+                list.append(value_into_py(py, value)?)?;
+            }
+            list.into()
+        }
+        Value::Map(entries) => {
+            let dict = PyDict::new(py);
+            for (key, value) in entries {
+                dict.set_item(map_key_into_py(py, key), value_into_py(py, value)?)?;
+            }
+            dict.into()
+        }
+    })
+}
+
+fn map_key_into_py(py: Python, key: &MapKey) -> PyObject {
+    match key {
+        MapKey::Bool(b) => b.to_object(py),
+        MapKey::I32(i) => i.to_object(py),
+        MapKey::I64(i) => i.to_object(py),
+        MapKey::U32(u) => u.to_object(py),
+        MapKey::U64(u) => u.to_object(py),
+        MapKey::String(s) => s.to_object(py),
+    }
+}
+
+fn message_from_py(descriptor: &MessageDescriptor, obj: &PyAny) -> PyResult<DynamicMessage> {
+    let dict = obj.downcast::<PyDict>().map_err(|_| {
+        PyTypeError::new_err(format!(
+            "Expected a dict to encode {}, found {}",
+            descriptor.full_name(),
+            obj.get_type().name().unwrap_or("<unknown>")
+        ))
+    })?;
+
+    let mut message = DynamicMessage::new(descriptor.clone());
+    for (key, value) in dict.iter() {
+        let name = key.downcast::<PyString>()?.to_str()?;
+        let field = descriptor.get_field_by_name(name).ok_or_else(|| {
+            PyValueError::new_err(format!(
+                "Unknown field {name} of {}",
+                descriptor.full_name()
+            ))
+        })?;
+
+        if value.is_none() {
+            continue;
+        }
+
+        let value = field_value_from_py(&field, value).map_err(|e| {
+            PyTypeError::new_err(format!(
+                "Invalid value for field {}: {}",
+                field.full_name(),
+                e.value(obj.py())
+            ))
+        })?;
+        message
+            .try_set_field(&field, value)
+            .map_err(|e| PyTypeError::new_err(e.to_string()))?;
+    }
+
+    Ok(message)
+}
+
+fn field_value_from_py(field: &FieldDescriptor, obj: &PyAny) -> PyResult<Value> {
+    if field.is_map() {
+        let entry = match field.kind() {
+            Kind::Message(entry) => entry,
+            _ => unreachable!("map fields are messages"),
+        };
+        let key_kind = entry.map_entry_key_field().kind();
+        let value_kind = entry.map_entry_value_field().kind();
+
+        let mut entries = HashMap::new();
+        for (key, value) in obj.downcast::<PyDict>()?.iter() {
+            let key = value_from_py(&key_kind, key)?
+                .into_map_key()
+                .ok_or_else(|| PyTypeError::new_err("Invalid map key"))?;
+            entries.insert(key, value_from_py(&value_kind, value)?);
+        }
+
+        Ok(Value::Map(entries))
+    } else if field.is_list() {
+        let kind = field.kind();
+        obj.iter()?
+            .map(|item| value_from_py(&kind, item?))
+            .collect::<PyResult<Vec<_>>>()
+            .map(Value::List)
+    } else {
+        value_from_py(&field.kind(), obj)
+    }
+}
+
+fn value_from_py(kind: &Kind, obj: &PyAny) -> PyResult<Value> {
+    Ok(match kind {
+        Kind::Double => Value::F64(obj.extract()?),
+        Kind::Float => Value::F32(obj.extract()?),
+        Kind::Int32 | Kind::Sint32 | Kind::Sfixed32 => Value::I32(obj.extract()?),
+        Kind::Int64 | Kind::Sint64 | Kind::Sfixed64 => Value::I64(obj.extract()?),
+        Kind::Uint32 | Kind::Fixed32 => Value::U32(obj.extract()?),
+        Kind::Uint64 | Kind::Fixed64 => Value::U64(obj.extract()?),
+        Kind::Bool => Value::Bool(obj.extract()?),
+        Kind::String => Value::String(obj.extract()?),
+        Kind::Bytes => Value::Bytes(Bytes::copy_from_slice(PyPayload::new(obj)?.as_ref())),
+        Kind::Message(descriptor) => Value::Message(message_from_py(descriptor, obj)?),
+        Kind::Enum(descriptor) => match obj.downcast::<PyString>() {
+            Ok(name) => {
+                let name = name.to_str()?;
+                let value = descriptor.get_value_by_name(name).ok_or_else(|| {
+                    PyValueError::new_err(format!(
+                        "Unknown value {name} of {}",
+                        descriptor.full_name()
+                    ))
+                })?;
+                Value::EnumNumber(value.number())
+            }
+            Err(_) => Value::EnumNumber(obj.extract()?),
+        },
+    })
+}
@@ -0,0 +1,257 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+//! The long-lived `run` lifecycle of Python nodes.
+//!
+//! While the node is started, its event loop runs in a dedicated thread on which the `run`
+//! coroutine is scheduled once. Background tasks, sockets and timers created by the node therefore
+//! keep running between iterations of Zenoh-Flow, and no Rust thread is blocked while the
+//! coroutine is running.
+//!
+//! Stopping the node cancels `run`, and the tasks it spawned, and stops the event loop: starting
+//! the node again schedules `run` again.
+
+use pyo3::exceptions::PyTypeError;
+use pyo3::prelude::*;
+use pyo3::types::{PyCFunction, PyDict, PyTuple};
+use std::sync::Mutex;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+/// How long we wait for the tasks of the node to handle their cancellation.
+const CANCEL_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Cancels all the tasks running on the event loop and waits for them to terminate.
+const SHUTDOWN: &str = r#"
+import asyncio
+
+async def shutdown(timeout):
+    tasks = [t for t in asyncio.all_tasks() if t is not asyncio.current_task()]
+    for task in tasks:
+        task.cancel()
+    if tasks:
+        await asyncio.wait(tasks, timeout=timeout)
+    await asyncio.get_running_loop().shutdown_asyncgens()
+"#;
+
+/// The `run` coroutine of a node, scheduled on its event loop.
+pub struct RunTask {
+    /// Receives the outcome of the coroutine, once.
+    done: flume::Receiver<PyResult<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl RunTask {
+    /// Starts `event_loop` in a dedicated thread and schedules the `run` coroutine of `node` on it.
+    pub(crate) fn spawn(
+        py: Python,
+        asyncio: &PyAny,
+        event_loop: &PyAny,
+        node: &PyAny,
+    ) -> PyResult<Self> {
+        let coroutine = node.call_method0("run")?;
+
+        let loop_hdl: PyObject = event_loop.into();
+        let thread = std::thread::Builder::new()
+            .name("python-event-loop".to_string())
+            .spawn(move || {
+                Python::with_gil(|py| {
+                    if let Err(e) = loop_hdl.call_method0(py, "run_forever") {
+                        log::error!("The event loop of the Python node stopped: {e}");
+                    }
+                })
+            })?;
+
+        let future = asyncio.call_method1("run_coroutine_threadsafe", (coroutine, event_loop))?;
+
+        let (tx, done) = flume::bounded(1);
+        let callback = PyCFunction::new_closure(
+            move |args: &PyTuple, _kwargs: Option<&PyDict>| -> PyResult<()> {
+                let outcome = args.get_item(0)?.call_method0("result").map(|_| ());
+                let _ = tx.send(outcome);
+                Ok(())
+            },
+            py,
+        )?;
+        future.call_method1("add_done_callback", (callback,))?;
+
+        Ok(Self {
+            done,
+            thread: Some(thread),
+        })
+    }
+
+    /// Returns the receiver of the outcome of the `run` coroutine.
+    ///
+    /// The outcome is received only once: afterwards the receiver is disconnected.
+    pub(crate) fn done(&self) -> flume::Receiver<PyResult<()>> {
+        self.done.clone()
+    }
+
+    /// Cancels the `run` coroutine and the tasks it spawned, if they are still running, then stops
+    /// the event loop and waits for its thread to terminate.
+    pub(crate) fn stop(&mut self, py: Python, asyncio: &PyAny, event_loop: &PyAny) -> PyResult<()> {
+        let shutdown = PyModule::from_code(py, SHUTDOWN, "zenoh_flow_shutdown.py", "shutdown")?
+            .getattr("shutdown")?
+            .call1((CANCEL_TIMEOUT.as_secs_f64(),))?;
+        asyncio
+            .call_method1("run_coroutine_threadsafe", (shutdown, event_loop))?
+            .call_method0("result")?;
+
+        event_loop.call_method1("call_soon_threadsafe", (event_loop.getattr("stop")?,))?;
+        if let Some(thread) = self.thread.take() {
+            if py.allow_threads(move || thread.join()).is_err() {
+                log::error!("The thread of the Python event loop panicked");
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns whether `node` defines a `run` coroutine.
+///
+/// # Errors
+///
+/// This function fails if `run` is not an `async def run(self)` coroutine function.
+pub(crate) fn has_run(py: Python, node: &PyAny) -> PyResult<bool> {
+    if !node.hasattr("run")? {
+        return Ok(false);
+    }
+
+    let run = node.getattr("run")?;
+    let is_coroutine_function = run.is_callable()
+        && py
+            .import("inspect")?
+            .call_method1("iscoroutinefunction", (run,))?
+            .is_true()?;
+    if !is_coroutine_function {
+        return Err(PyTypeError::new_err(format!(
+            "`run` must be an `async def run(self)` coroutine function, found a {}",
+            run.get_type().name()?
+        )));
+    }
+
+    Ok(true)
+}
+
+/// Stops the `run` coroutine of the node, if it is scheduled, logging the errors instead of
+/// returning them.
+pub(crate) fn stop_run_task(
+    py: Python,
+    run_task: &Mutex<Option<RunTask>>,
+    asyncio: &PyAny,
+    event_loop: &PyAny,
+) {
+    let run_task = match run_task.lock() {
+        Ok(mut run_task) => run_task.take(),
+        Err(e) => {
+            log::error!("Python run task poisoned: {e}");
+            return;
+        }
+    };
+
+    if let Some(mut run_task) = run_task {
+        if let Err(e) = run_task.stop(py, asyncio, event_loop) {
+            log::error!("Unable to stop the Python event loop: {e}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PythonState;
+    use std::sync::Arc;
+
+    const NODE: &str = r#"
+import asyncio
+
+events = []
+
+class Node:
+    async def run(self):
+        events.append("run")
+        try:
+            await asyncio.sleep(3600)
+        except asyncio.CancelledError:
+            events.append("cancelled")
+            raise
+
+    def on_stop(self):
+        events.append("on_stop")
+"#;
+
+    #[test]
+    fn stopping_the_node_cancels_run_and_starting_it_reschedules_run() {
+        pyo3::prepare_freethreaded_python();
+        let (state, module) = Python::with_gil(|py| {
+            let module = PyModule::from_code(py, NODE, "node.py", "node").unwrap();
+            let node = module.getattr("Node").unwrap().call0().unwrap();
+            let asyncio = py.import("asyncio").unwrap();
+            let event_loop = asyncio.call_method0("new_event_loop").unwrap();
+            let state = PythonState {
+                module: Arc::new(module.getattr("Node").unwrap().into()),
+                py_state: Arc::new(node.into()),
+                event_loop: Arc::new(event_loop.into()),
+                asyncio_module: Arc::new(asyncio.into()),
+                run_task: Default::default(),
+                has_run: has_run(py, node).unwrap(),
+                finalize_timeout: crate::FINALIZE_TIMEOUT,
+                node_id: None,
+            };
+            (state, PyObject::from(module))
+        });
+
+        for _ in 0..2 {
+            // Zenoh Flow stops a node by dropping its iteration.
+            let stopped = async_std::task::block_on(async_std::future::timeout(
+                Duration::from_millis(200),
+                state.iteration(),
+            ));
+            assert!(stopped.is_err());
+        }
+
+        Python::with_gil(|py| {
+            let events: Vec<String> = module.getattr(py, "events").unwrap().extract(py).unwrap();
+            assert_eq!(
+                events,
+                ["run", "cancelled", "on_stop", "run", "cancelled", "on_stop"]
+            );
+        });
+    }
+
+    #[test]
+    fn run_must_be_a_coroutine_function() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let module = PyModule::from_code(
+                py,
+                "class NoRun:\n    pass\n\
+                 class SyncRun:\n    def run(self):\n        pass\n\
+                 class NotCallable:\n    run = 3\n",
+                "nodes.py",
+                "nodes",
+            )
+            .unwrap();
+            let node = |name: &str| module.getattr(name).unwrap().call0().unwrap();
+
+            assert!(!has_run(py, node("NoRun")).unwrap());
+            for name in ["SyncRun", "NotCallable"] {
+                let err = has_run(py, node(name)).unwrap_err();
+                assert!(err.is_instance_of::<PyTypeError>(py), "{}: {}", name, err);
+            }
+        });
+    }
+}
@@ -0,0 +1,389 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+//! Validation of the configuration of a Python node against the JSON Schema it declares.
+//!
+//! A node declares its schema with the `configuration_schema` attribute of its class. The
+//! supported keywords are:
+//! - `type` (a type name or a list of type names),
+//! - `enum` and `const`,
+//! - `minimum`, `maximum`, `exclusiveMinimum` and `exclusiveMaximum`,
+//! - `minLength` and `maxLength`,
+//! - `items`, `minItems` and `maxItems`,
+//! - `properties`, `required` and `additionalProperties`.
+//!
+//! The annotations `title`, `description`, `default`, `examples`, `$schema`, `$id` and `$comment`
+//! are ignored, any other keyword is rejected, as is a keyword given a value of the wrong type,
+//! e.g. `"minimum": "0"`.
+
+use crate::configuration_from_py;
+use pyo3::exceptions::PyAttributeError;
+use pyo3::prelude::*;
+use zenoh_flow::prelude::{zferror, Configuration, ErrorKind, Result};
+
+const ANNOTATIONS: [&str; 7] = [
+    "title",
+    "description",
+    "default",
+    "examples",
+    "$schema",
+    "$id",
+    "$comment",
+];
+
+/// Returns the schema declared by the Python node `class`, if any.
+///
+/// # Errors
+///
+/// This function fails if getting the `configuration_schema` attribute raises an exception other
+/// than an `AttributeError`, or if the schema cannot be converted.
+pub fn configuration_schema(class: &PyAny) -> PyResult<Option<Configuration>> {
+    match class.getattr("configuration_schema") {
+        Ok(schema) if schema.is_none() => Ok(None),
+        Ok(schema) => configuration_from_py(schema).map(Some),
+        Err(e) if e.is_instance_of::<PyAttributeError>(class.py()) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Validates the `configuration` of a node against its `schema`.
+///
+/// # Errors
+///
+/// This function fails with an error giving the path, in the schema, of the keyword that is not
+/// supported or whose value has the wrong type, or else the path, in the configuration, of the
+/// first value that does not match the schema.
+pub fn validate_configuration(schema: &Configuration, configuration: &Configuration) -> Result<()> {
+    check_schema(schema, "").map_err(|(path, reason)| {
+        let path = if path.is_empty() { "/" } else { &path };
+        zferror!(
+            ErrorKind::ConfigurationError,
+            "Invalid configuration schema at {path}: {reason}"
+        )
+    })?;
+
+    validate(schema, configuration, "").map_err(|(path, reason)| {
+        let path = if path.is_empty() { "/" } else { &path };
+        zferror!(
+            ErrorKind::ConfigurationError,
+            "Invalid configuration at {path}: {reason}"
+        )
+        .into()
+    })
+}
+
+type Invalid = (String, String);
+
+fn invalid(path: &str, reason: String) -> std::result::Result<(), Invalid> {
+    Err((path.to_string(), reason))
+}
+
+/// Checks that the `schema` only uses supported keywords, with values of the expected types.
+fn check_schema(schema: &Configuration, path: &str) -> std::result::Result<(), Invalid> {
+    let schema = match schema {
+        Configuration::Bool(_) => return Ok(()),
+        Configuration::Object(schema) => schema,
+        _ => {
+            return invalid(
+                path,
+                "the schema is neither an object nor a bool".to_string(),
+            )
+        }
+    };
+
+    for (keyword, expected) in schema {
+        let keyword_path = format!("{path}/{keyword}");
+        let valid = match keyword.as_str() {
+            "type" => match expected {
+                Configuration::Array(types) => types.iter().all(is_type_name),
+                name => is_type_name(name),
+            },
+            "enum" => expected.is_array(),
+            "const" => true,
+            "minimum" | "maximum" | "exclusiveMinimum" | "exclusiveMaximum" => expected.is_number(),
+            "minLength" | "maxLength" | "minItems" | "maxItems" => expected.is_u64(),
+            "items" | "additionalProperties" => {
+                check_schema(expected, &keyword_path)?;
+                true
+            }
+            "required" => expected
+                .as_array()
+                .map_or(false, |keys| keys.iter().all(Configuration::is_string)),
+            "properties" => match expected.as_object() {
+                Some(properties) => {
+                    for (key, property) in properties {
+                        check_schema(property, &format!("{keyword_path}/{key}"))?;
+                    }
+                    true
+                }
+                None => false,
+            },
+            keyword if ANNOTATIONS.contains(&keyword) => true,
+            keyword => {
+                return invalid(
+                    path,
+                    format!("the schema keyword {keyword} is not supported"),
+                )
+            }
+        };
+
+        if !valid {
+            return invalid(
+                &keyword_path,
+                format!("invalid value {expected} for the schema keyword {keyword}"),
+            );
+        }
+    }
+
+    Ok(())
+}
+
+fn validate(
+    schema: &Configuration,
+    value: &Configuration,
+    path: &str,
+) -> std::result::Result<(), Invalid> {
+    let schema = match schema {
+        Configuration::Bool(true) => return Ok(()),
+        Configuration::Bool(false) => return invalid(path, "no value is allowed".to_string()),
+        Configuration::Object(schema) => schema,
+        _ => {
+            return invalid(
+                path,
+                "the schema is neither an object nor a bool".to_string(),
+            )
+        }
+    };
+
+    for (keyword, expected) in schema {
+        match keyword.as_str() {
+            "type" => {
+                let types = match expected {
+                    Configuration::Array(types) => types.iter().collect(),
+                    name => vec![name],
+                };
+                if !types.iter().any(|name| has_type(value, name)) {
+                    return invalid(path, format!("expected type {expected}, found {value}"));
+                }
+            }
+            "enum" => {
+                let allowed = expected.as_array().map(Vec::as_slice).unwrap_or_default();
+                if !allowed.contains(value) {
+                    return invalid(path, format!("expected one of {expected}, found {value}"));
+                }
+            }
+            "const" => {
+                if value != expected {
+                    return invalid(path, format!("expected {expected}, found {value}"));
+                }
+            }
+            "minimum" | "maximum" | "exclusiveMinimum" | "exclusiveMaximum" => {
+                if let (Some(bound), Some(number)) = (expected.as_f64(), value.as_f64()) {
+                    let ok = match keyword.as_str() {
+                        "minimum" => number >= bound,
+                        "maximum" => number <= bound,
+                        "exclusiveMinimum" => number > bound,
+                        _ => number < bound,
+                    };
+                    if !ok {
+                        return invalid(path, format!("{value} violates {keyword} {expected}"));
+                    }
+                }
+            }
+            "minLength" | "maxLength" => {
+                if let (Some(bound), Some(string)) = (expected.as_u64(), value.as_str()) {
+                    let length = string.chars().count() as u64;
+                    if (keyword == "minLength" && length < bound)
+                        || (keyword == "maxLength" && length > bound)
+                    {
+                        return invalid(path, format!("{value} violates {keyword} {expected}"));
+                    }
+                }
+            }
+            "minItems" | "maxItems" => {
+                if let (Some(bound), Some(items)) = (expected.as_u64(), value.as_array()) {
+                    let length = items.len() as u64;
+                    if (keyword == "minItems" && length < bound)
+                        || (keyword == "maxItems" && length > bound)
+                    {
+                        return invalid(
+                            path,
+                            format!("{length} items violate {keyword} {expected}"),
+                        );
+                    }
+                }
+            }
+            "items" => {
+                if let Some(items) = value.as_array() {
+                    for (index, item) in items.iter().enumerate() {
+                        validate(expected, item, &format!("{path}/{index}"))?;
+                    }
+                }
+            }
+            "required" => {
+                if let Some(object) = value.as_object() {
+                    let required = expected.as_array().map(Vec::as_slice).unwrap_or_default();
+                    for key in required.iter().filter_map(Configuration::as_str) {
+                        if !object.contains_key(key) {
+                            return invalid(path, format!("missing required property {key}"));
+                        }
+                    }
+                }
+            }
+            "properties" => {
+                if let (Some(properties), Some(object)) = (expected.as_object(), value.as_object())
+                {
+                    for (key, property) in properties {
+                        if let Some(value) = object.get(key) {
+                            validate(property, value, &format!("{path}/{key}"))?;
+                        }
+                    }
+                }
+            }
+            "additionalProperties" => {
+                if let Some(object) = value.as_object() {
+                    let properties = schema.get("properties").and_then(Configuration::as_object);
+                    for (key, value) in object {
+                        if !properties.map_or(false, |properties| properties.contains_key(key)) {
+                            validate(expected, value, &format!("{path}/{key}")).map_err(
+                                |(path, reason)| match expected {
+                                    Configuration::Bool(false) => {
+                                        (path, "additional property not allowed".to_string())
+                                    }
+                                    _ => (path, reason),
+                                },
+                            )?;
+                        }
+                    }
+                }
+            }
+            // Annotations, the other keywords are rejected by `check_schema`.
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+fn is_type_name(name: &Configuration) -> bool {
+    matches!(
+        name.as_str(),
+        Some("null" | "boolean" | "object" | "array" | "number" | "integer" | "string")
+    )
+}
+
+fn has_type(value: &Configuration, name: &Configuration) -> bool {
+    match name.as_str() {
+        Some("null") => value.is_null(),
+        Some("boolean") => value.is_boolean(),
+        Some("object") => value.is_object(),
+        Some("array") => value.is_array(),
+        Some("number") => value.is_number(),
+        Some("integer") => {
+            value.is_i64() || value.is_u64() || value.as_f64().map_or(false, |f| f.fract() == 0.0)
+        }
+        Some("string") => value.is_string(),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn schema() -> Configuration {
+        json!({
+            "type": "object",
+            "properties": {
+                "threshold": { "type": "number", "minimum": 0 },
+                "labels": { "type": "array", "items": { "type": "string" } },
+                "mode": { "enum": ["fast", "accurate"] },
+            },
+            "required": ["threshold"],
+            "additionalProperties": false,
+        })
+    }
+
+    fn error(configuration: Configuration) -> String {
+        validate_configuration(&schema(), &configuration)
+            .unwrap_err()
+            .to_string()
+    }
+
+    #[test]
+    fn valid_configurations_are_accepted() {
+        let configuration = json!({ "threshold": 0.5, "labels": ["cat"], "mode": "fast" });
+        assert!(validate_configuration(&schema(), &configuration).is_ok());
+        assert!(validate_configuration(&json!(true), &configuration).is_ok());
+    }
+
+    #[test]
+    fn errors_give_the_path_of_the_invalid_value() {
+        assert!(error(json!({ "threshold": -1 })).contains("at /threshold"));
+        assert!(error(json!({ "threshold": 1, "labels": ["cat", 2] })).contains("at /labels/1"));
+        assert!(error(json!({ "threshold": 1, "mode": "slow" })).contains("at /mode"));
+        assert!(error(json!({ "threshold": 1, "extra": 1 })).contains("at /extra"));
+        assert!(error(json!({})).contains("at /"));
+        assert!(error(json!([])).contains("at /"));
+    }
+
+    #[test]
+    fn unsupported_keywords_are_rejected() {
+        let schema = json!({ "properties": { "name": { "pattern": "^a" } } });
+        let err = validate_configuration(&schema, &json!({ "name": "abc" })).unwrap_err();
+        assert!(err.to_string().contains("/name"), "{}", err);
+        assert!(err.to_string().contains("pattern"), "{}", err);
+    }
+
+    #[test]
+    fn keyword_values_of_the_wrong_type_are_rejected() {
+        for schema in [
+            json!({ "properties": { "threshold": { "minimum": "0" } } }),
+            json!({ "properties": { "threshold": { "type": "float" } } }),
+            json!({ "items": { "maxItems": -1 } }),
+            json!({ "required": "threshold" }),
+            json!({ "properties": ["threshold"] }),
+            json!({ "additionalProperties": 3 }),
+        ] {
+            let err = validate_configuration(&schema, &json!({})).unwrap_err();
+            assert!(
+                err.to_string().contains("Invalid configuration schema"),
+                "{}: {}",
+                schema,
+                err
+            );
+        }
+    }
+
+    #[test]
+    fn only_a_missing_schema_attribute_is_no_schema() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let module = PyModule::from_code(
+                py,
+                "class Plain:\n    pass\n\nclass Failing:\n    @property\n    def configuration_schema(self):\n        raise ValueError('no schema')\n",
+                "node.py",
+                "node",
+            )
+            .unwrap();
+
+            let plain = module.getattr("Plain").unwrap();
+            assert_eq!(configuration_schema(plain).unwrap(), None);
+            let failing = module.getattr("Failing").unwrap().call0().unwrap();
+            let err = configuration_schema(failing).unwrap_err();
+            assert!(err.is_instance_of::<pyo3::exceptions::PyValueError>(py));
+        });
+    }
+}
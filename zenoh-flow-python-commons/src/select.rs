@@ -0,0 +1,190 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+use crate::{arrow, decode_buffer, ndarray, RawInput, RawMessage};
+use futures::lock::Mutex;
+use futures::stream::{self, BoxStream, SelectAll, StreamExt};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use std::future::Future;
+use std::sync::Arc;
+use zenoh_flow::prelude::Result;
+use zenoh_flow::types::{LinkMessage, Payload};
+
+type Decode = fn(Python, &Payload) -> PyResult<PyObject>;
+
+/// A message, or the error ending the stream, received on the input at the given index.
+type Received<T> = (usize, Result<T>);
+
+/// Waits, *asynchronously*, on several Inputs at once.
+///
+/// Each Input is associated with the encoding of the data it receives: `"bytes"` (the data is a
+/// `memoryview`), `"ndarray"` or `"arrow"`.
+///
+/// The Inputs are received from by streams that live as long as the `RawSelect`: a call to `recv`
+/// resumes the receptions left pending by the previous one. The Input that delivered a message is
+/// polled after the others that are ready: when messages are available on several Inputs, an Input
+/// cannot be starved by the others.
+#[pyclass]
+pub struct RawSelect {
+    ports: Arc<Vec<(String, Decode)>>,
+    receivers: Arc<Mutex<SelectAll<BoxStream<'static, Received<LinkMessage>>>>>,
+}
+
+#[pymethods]
+impl RawSelect {
+    #[new]
+    pub fn new(inputs: Vec<(PyRef<RawInput>, &str)>) -> PyResult<Self> {
+        if inputs.is_empty() {
+            return Err(PyValueError::new_err("Cannot select over no Input"));
+        }
+
+        let mut ports = Vec::with_capacity(inputs.len());
+        let mut receivers = SelectAll::new();
+        for (index, (input, encoding)) in inputs.iter().enumerate() {
+            let decode: Decode = match *encoding {
+                "bytes" => decode_buffer,
+                "ndarray" => ndarray::decode,
+                "arrow" => arrow::decode,
+                _ => {
+                    return Err(PyValueError::new_err(format!(
+                        "Unknown encoding {encoding}, expected one of: bytes, ndarray, arrow"
+                    )))
+                }
+            };
+            let input = input.receiver.clone();
+            ports.push((input.port_id().to_string(), decode));
+            receivers.push(receive(index, move || {
+                let input = input.clone();
+                async move { input.recv().await }
+            }));
+        }
+
+        Ok(Self {
+            ports: Arc::new(ports),
+            receivers: Arc::new(Mutex::new(receivers)),
+        })
+    }
+
+    /// Returns, *asynchronously*, a tuple `(port_id, RawDataMessage)` with the first message that
+    /// was received on any of the Inputs.
+    ///
+    /// Inputs whose channels are all disconnected are ignored, which is logged once, an error is
+    /// raised when it is the case of all of them.
+    pub fn recv<'p>(&'p self, py: Python<'p>) -> PyResult<&'p PyAny> {
+        let ports = self.ports.clone();
+        let receivers = self.receivers.clone();
+
+        pyo3_asyncio::async_std::future_into_py(py, async move {
+            let mut receivers = receivers.lock().await;
+            while let Some((index, res)) = receivers.next().await {
+                let (port_id, decode) = &ports[index];
+                match res {
+                    Ok(rust_msg) => {
+                        let msg = RawMessage::try_from_with(rust_msg, *decode)?;
+                        return Ok((port_id.clone(), msg));
+                    }
+                    Err(e) => log::error!("Input {port_id} is disconnected, ignoring it: {e}"),
+                }
+            }
+
+            Err(PyValueError::new_err(
+                "Unable to receive data: all Inputs are disconnected",
+            ))
+        })
+    }
+}
+
+/// Returns the stream of the results of `recv`, tagged with the `index` of the input, which ends
+/// after the first error: the input is then disconnected.
+fn receive<T, F, Fut>(index: usize, recv: F) -> BoxStream<'static, Received<T>>
+where
+    T: Send + 'static,
+    F: Fn() -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<T>> + Send + 'static,
+{
+    stream::unfold(Some(recv), move |recv| async move {
+        let recv = recv?;
+        match recv().await {
+            Ok(message) => Some(((index, Ok(message)), Some(recv))),
+            Err(e) => Some(((index, Err(e)), None)),
+        }
+    })
+    .boxed()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+    use zenoh_flow::prelude::{zferror, ErrorKind};
+
+    fn channel(
+        index: usize,
+        messages: &[u32],
+    ) -> (flume::Sender<u32>, BoxStream<'static, Received<u32>>) {
+        let (sender, receiver) = flume::unbounded();
+        for message in messages {
+            sender.send(*message).unwrap();
+        }
+        let stream = receive(index, move || {
+            let receiver = receiver.clone();
+            async move {
+                receiver
+                    .recv_async()
+                    .await
+                    .map_err(|e| zferror!(ErrorKind::Disconnected, e).into())
+            }
+        });
+        (sender, stream)
+    }
+
+    fn indices(received: &[Received<u32>]) -> Vec<(usize, bool)> {
+        received
+            .iter()
+            .map(|(index, res)| (*index, res.is_ok()))
+            .collect()
+    }
+
+    #[test]
+    fn ready_inputs_are_served_in_turn() {
+        let (_a, a) = channel(0, &[1, 2, 3]);
+        let (_b, b) = channel(1, &[1, 2, 3]);
+        let mut receivers = SelectAll::new();
+        receivers.push(a);
+        receivers.push(b);
+
+        let received = block_on(receivers.by_ref().take(4).collect::<Vec<_>>());
+        assert_eq!(
+            indices(&received),
+            [(0, true), (1, true), (0, true), (1, true)]
+        );
+    }
+
+    #[test]
+    fn disconnected_inputs_are_reported_once() {
+        let (a_sender, a) = channel(0, &[1]);
+        let (b_sender, b) = channel(1, &[]);
+        drop(a_sender);
+        let mut receivers = SelectAll::new();
+        receivers.push(a);
+        receivers.push(b);
+
+        let received = block_on(receivers.by_ref().take(2).collect::<Vec<_>>());
+        assert_eq!(indices(&received), [(0, true), (0, false)]);
+        drop(b_sender);
+        let received = block_on(receivers.collect::<Vec<_>>());
+        assert_eq!(indices(&received), [(1, false)]);
+    }
+}
@@ -12,16 +12,8 @@
 //   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
 //
 
-fn get_py_lib_name() -> String {
-    let config = pyo3_build_config::get();
-    match &config.lib_name {
-        Some(name) => name.clone(),
-        None => panic!("Unable to find Python version"),
-    }
-}
-
 fn main() {
-    let py = get_py_lib_name();
-    println!("cargo:rustc-env=PY_LIB={py}");
-    println!("cargo:rustc-link-lib=dylib={py}");
+    // The Python nodes are built as a Python extension module, without linking the Python library:
+    // its symbols are those the wrappers made global, see `zenoh-flow-python-shim`.
+    pyo3_build_config::add_extension_module_link_args();
 }
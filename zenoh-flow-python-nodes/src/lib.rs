@@ -0,0 +1,86 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+//! The Python source, sink and operator, created by the wrappers once they loaded the Python
+//! library, see `zenoh-flow-python-shim`.
+
+use async_trait::async_trait;
+use zenoh_flow::prelude::*;
+use zenoh_flow_python_commons::{NodeKind, PythonNode};
+
+#[export_source]
+struct PySource(PythonNode);
+
+#[async_trait]
+impl Source for PySource {
+    async fn new(
+        context: Context,
+        configuration: Option<Configuration>,
+        outputs: Outputs,
+    ) -> Result<Self> {
+        PythonNode::new(NodeKind::Source(outputs), context, configuration).map(Self)
+    }
+}
+
+#[async_trait]
+impl Node for PySource {
+    async fn iteration(&self) -> Result<()> {
+        self.0.iteration().await
+    }
+}
+
+#[export_sink]
+#[derive(Debug)]
+struct PySink(PythonNode);
+
+#[async_trait]
+impl Sink for PySink {
+    async fn new(
+        context: Context,
+        configuration: Option<Configuration>,
+        inputs: Inputs,
+    ) -> Result<Self> {
+        PythonNode::new(NodeKind::Sink(inputs), context, configuration).map(Self)
+    }
+}
+
+#[async_trait]
+impl Node for PySink {
+    async fn iteration(&self) -> Result<()> {
+        self.0.iteration().await
+    }
+}
+
+#[export_operator]
+#[derive(Clone)]
+struct PyOperator(PythonNode);
+
+#[async_trait]
+impl Operator for PyOperator {
+    async fn new(
+        context: Context,
+        configuration: Option<Configuration>,
+        inputs: Inputs,
+        outputs: Outputs,
+    ) -> Result<Self> {
+        PythonNode::new(NodeKind::Operator(inputs, outputs), context, configuration).map(Self)
+    }
+}
+
+#[async_trait]
+impl Node for PyOperator {
+    async fn iteration(&self) -> Result<()> {
+        self.0.iteration().await
+    }
+}
@@ -21,7 +21,8 @@ fn get_py_lib_name() -> String {
 }
 
 fn main() {
+    // The Python library is not linked: it is loaded at runtime, see `load_libpython`, the library
+    // the wrappers are built against being the last fallback.
     let py = get_py_lib_name();
     println!("cargo:rustc-env=PY_LIB={py}");
-    println!("cargo:rustc-link-lib=dylib={py}");
 }
@@ -0,0 +1,222 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+//! The constructors of the source, sink and operator wrappers.
+//!
+//! Zenoh Flow opens the wrappers with `RTLD_NOW | RTLD_LOCAL`: a library using the symbols of
+//! Python without being linked against it, like a Python extension module, cannot be opened this
+//! way. The wrappers are thus shims without any Python symbol. When their first node is created,
+//! they load the Python library with its symbols made global, see [`load_libpython`], then the
+//! library of the Python nodes, `zenoh_flow_python_nodes`, from their own directory. The node is
+//! created by the constructor that this library exports for its kind.
+//!
+//! Both libraries stay loaded until the process exits.
+
+mod libpython;
+
+pub use libpython::{load_libpython, Library, PYTHON_SYS_EXECUTABLE};
+
+use once_cell::sync::OnceCell;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::Arc;
+use zenoh_flow::bail;
+use zenoh_flow::prelude::{
+    zferror, Configuration, Context, ErrorKind, Inputs, Node, Outputs, Result,
+};
+use zenoh_flow::runtime::dataflow::loader::{NodeDeclaration, CORE_VERSION, RUSTC_VERSION};
+use zenoh_flow::runtime::dataflow::node::{OperatorFn, SinkFn, SourceFn};
+
+/// The Python library the wrappers were built against, loaded if no interpreter gives its own.
+pub static PY_LIB: &str = env!("PY_LIB");
+
+/// The name of the library of the Python nodes.
+pub const NODES_LIB: &str = "zenoh_flow_python_nodes";
+
+/// The symbols of the declarations of the nodes, as exported by Zenoh Flow.
+pub const SOURCE_SYMBOL: &[u8] = b"_zf_export_source\0";
+pub const SINK_SYMBOL: &[u8] = b"_zf_export_sink\0";
+pub const OPERATOR_SYMBOL: &[u8] = b"_zf_export_operator\0";
+
+#[cfg(target_family = "unix")]
+static LOAD_FLAGS: std::os::raw::c_int =
+    libloading::os::unix::RTLD_NOW | libloading::os::unix::RTLD_LOCAL;
+
+static LIBPYTHON: OnceCell<Library> = OnceCell::new();
+static NODES: OnceCell<Library> = OnceCell::new();
+
+type NodeFuture = Pin<Box<dyn Future<Output = Result<Arc<dyn Node>>> + Send>>;
+
+/// Creates a Python source, see [`SourceFn`].
+pub fn source(
+    context: Context,
+    configuration: Option<Configuration>,
+    outputs: Outputs,
+) -> NodeFuture {
+    match declaration::<SourceFn>(SOURCE_SYMBOL) {
+        Ok(declaration) => (declaration.constructor)(context, configuration, outputs),
+        Err(e) => Box::pin(async { Err(e) }),
+    }
+}
+
+/// Creates a Python sink, see [`SinkFn`].
+pub fn sink(context: Context, configuration: Option<Configuration>, inputs: Inputs) -> NodeFuture {
+    match declaration::<SinkFn>(SINK_SYMBOL) {
+        Ok(declaration) => (declaration.constructor)(context, configuration, inputs),
+        Err(e) => Box::pin(async { Err(e) }),
+    }
+}
+
+/// Creates a Python operator, see [`OperatorFn`].
+pub fn operator(
+    context: Context,
+    configuration: Option<Configuration>,
+    inputs: Inputs,
+    outputs: Outputs,
+) -> NodeFuture {
+    match declaration::<OperatorFn>(OPERATOR_SYMBOL) {
+        Ok(declaration) => (declaration.constructor)(context, configuration, inputs, outputs),
+        Err(e) => Box::pin(async { Err(e) }),
+    }
+}
+
+/// Reads the declaration `symbol` of the library of the Python nodes, loading it first.
+///
+/// # Errors
+///
+/// This function fails if a library cannot be loaded, if the declaration is not found or if the
+/// library of the Python nodes was not built with the same versions of Rust and Zenoh Flow.
+fn declaration<C>(symbol: &[u8]) -> Result<NodeDeclaration<C>> {
+    let nodes = load_nodes()?;
+    let declaration = unsafe { nodes.get::<*mut NodeDeclaration<C>>(symbol)?.read() };
+
+    if declaration.rustc_version != RUSTC_VERSION || declaration.core_version != CORE_VERSION {
+        bail!(
+            ErrorKind::VersionMismatch,
+            "{NODES_LIB} rustc expected {RUSTC_VERSION} rustc found {} - Zenoh-Flow expected {CORE_VERSION} Zenoh-Flow found {}",
+            declaration.rustc_version,
+            declaration.core_version
+        );
+    }
+
+    Ok(declaration)
+}
+
+/// Loads, once, the Python library and then the library of the Python nodes.
+///
+/// # Errors
+///
+/// This function fails if the Python library or the library of the Python nodes cannot be loaded.
+pub fn load_nodes() -> Result<&'static Library> {
+    NODES.get_or_try_init(|| {
+        LIBPYTHON.get_or_try_init(|| load_libpython(PY_LIB))?;
+
+        let path = nodes_path();
+        let nodes = open_local(&path).map_err(|e| {
+            zferror!(
+                ErrorKind::LoadingError,
+                "Unable to load the Python nodes {}: {e}",
+                path.display()
+            )
+        })?;
+        log::debug!("Loaded the Python nodes {}", path.display());
+        Ok(nodes)
+    })
+}
+
+/// Returns the path of the library of the Python nodes, in the directory of the wrapper, or its
+/// file name if the directory of the wrapper is unknown.
+fn nodes_path() -> PathBuf {
+    let file_name = libloading::library_filename(NODES_LIB);
+    match wrapper_dir() {
+        Some(dir) => dir.join(file_name),
+        None => PathBuf::from(file_name),
+    }
+}
+
+/// Returns the directory of the library this function is part of.
+#[cfg(target_family = "unix")]
+fn wrapper_dir() -> Option<PathBuf> {
+    use std::ffi::{CStr, OsStr};
+    use std::os::unix::ffi::OsStrExt;
+
+    let mut info = std::mem::MaybeUninit::<libc::Dl_info>::uninit();
+    let info = unsafe {
+        if libc::dladdr(wrapper_dir as *const libc::c_void, info.as_mut_ptr()) == 0 {
+            return None;
+        }
+        info.assume_init()
+    };
+    if info.dli_fname.is_null() {
+        return None;
+    }
+
+    let path = unsafe { CStr::from_ptr(info.dli_fname) };
+    Path::new(OsStr::from_bytes(path.to_bytes()))
+        .parent()
+        .map(Path::to_path_buf)
+}
+
+#[cfg(not(target_family = "unix"))]
+fn wrapper_dir() -> Option<PathBuf> {
+    None
+}
+
+/// Opens the library at `path` the way Zenoh Flow opens the wrappers.
+fn open_local(path: &Path) -> std::result::Result<Library, libloading::Error> {
+    unsafe {
+        #[cfg(target_family = "unix")]
+        let lib = Library::open(Some(path), LOAD_FLAGS);
+
+        #[cfg(target_family = "windows")]
+        let lib = Library::new(path);
+
+        lib
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the directory of the libraries built along with the tests, the parent of `deps`.
+    fn target_dir() -> PathBuf {
+        let test = std::env::current_exe().unwrap();
+        test.parent().unwrap().parent().unwrap().to_path_buf()
+    }
+
+    #[test]
+    fn wrappers_load_as_zenoh_flow_loads_them() {
+        let dir = target_dir();
+        for (wrapper, symbol) in [
+            ("zenoh_flow_python_source_wrapper", SOURCE_SYMBOL),
+            ("zenoh_flow_python_sink_wrapper", SINK_SYMBOL),
+            ("zenoh_flow_python_operator_wrapper", OPERATOR_SYMBOL),
+        ] {
+            let path = dir.join(libloading::library_filename(wrapper));
+            let lib = open_local(&path)
+                .unwrap_or_else(|e| panic!("{} (build the workspace first): {}", wrapper, e));
+            assert!(unsafe { lib.get::<*mut u8>(symbol) }.is_ok(), "{}", wrapper);
+        }
+
+        // The Python nodes can only be loaded once the symbols of Python are global, which
+        // `declaration` does first. The nodes are found next to the tests, in `deps`.
+        let path = dir.join(libloading::library_filename(NODES_LIB));
+        assert!(open_local(&path).is_err());
+        assert!(declaration::<SourceFn>(SOURCE_SYMBOL).is_ok());
+        assert!(declaration::<SinkFn>(SINK_SYMBOL).is_ok());
+        assert!(declaration::<OperatorFn>(OPERATOR_SYMBOL).is_ok());
+    }
+}
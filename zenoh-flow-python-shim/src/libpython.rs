@@ -0,0 +1,144 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+//! Discovery, at runtime, of the Python shared library.
+//!
+//! The wrappers load the Python shared library with its symbols made global, so that the library
+//! of the Python nodes and the Python extension modules (e.g. NumPy) imported by the nodes can
+//! resolve them. The library is searched, in order:
+//! 1. through the interpreter given by the `PYTHON_SYS_EXECUTABLE` environment variable,
+//! 2. through the `python3` interpreter found in the `PATH`,
+//! 3. with the name of the library the wrapper was built against.
+//!
+//! An interpreter gives the location of its library through `sysconfig`. If no library can be
+//! loaded, the error lists all the attempts.
+
+use std::ffi::OsString;
+use std::path::PathBuf;
+use std::process::Command;
+use zenoh_flow::prelude::{zferror, ErrorKind, Result};
+
+#[cfg(target_family = "unix")]
+pub use libloading::os::unix::Library;
+#[cfg(target_family = "windows")]
+pub use libloading::Library;
+
+#[cfg(target_family = "unix")]
+static LOAD_FLAGS: std::os::raw::c_int =
+    libloading::os::unix::RTLD_NOW | libloading::os::unix::RTLD_GLOBAL;
+
+/// The environment variable giving the Python interpreter whose library is loaded.
+pub const PYTHON_SYS_EXECUTABLE: &str = "PYTHON_SYS_EXECUTABLE";
+
+const SYSCONFIG_PROBE: &str = "import sysconfig; \
+    print(sysconfig.get_config_var('LIBDIR') or ''); \
+    print(sysconfig.get_config_var('INSTSONAME') or sysconfig.get_config_var('LDLIBRARY') or '')";
+
+/// Loads the Python shared library, falling back to `build_lib`, the name of the library the
+/// wrappers were built against.
+///
+/// # Errors
+///
+/// This function fails, listing what was tried, if no Python shared library can be loaded.
+pub fn load_libpython(build_lib: &str) -> Result<Library> {
+    let mut attempts = Vec::new();
+
+    let mut interpreters = Vec::new();
+    match std::env::var_os(PYTHON_SYS_EXECUTABLE) {
+        Some(interpreter) => interpreters.push(interpreter),
+        None => attempts.push(format!("{PYTHON_SYS_EXECUTABLE}: not set")),
+    }
+    interpreters.push(OsString::from("python3"));
+
+    for interpreter in interpreters {
+        match probe_interpreter(&interpreter) {
+            Ok(candidates) => {
+                for candidate in candidates {
+                    match open(candidate.clone().into_os_string()) {
+                        Ok(lib) => {
+                            log::info!(
+                                "Loaded Python library {} of {}",
+                                candidate.display(),
+                                interpreter.to_string_lossy()
+                            );
+                            return Ok(lib);
+                        }
+                        Err(e) => attempts.push(format!("{}: {e}", candidate.display())),
+                    }
+                }
+            }
+            Err(e) => attempts.push(format!("{}: {e}", interpreter.to_string_lossy())),
+        }
+    }
+
+    let lib_name = libloading::library_filename(build_lib);
+    match open(lib_name.clone()) {
+        Ok(lib) => {
+            log::info!("Loaded Python library {}", lib_name.to_string_lossy());
+            Ok(lib)
+        }
+        Err(e) => {
+            attempts.push(format!("{}: {e}", lib_name.to_string_lossy()));
+            Err(zferror!(
+                ErrorKind::NotFound,
+                "Unable to load the Python library, tried:\n  {}",
+                attempts.join("\n  ")
+            )
+            .into())
+        }
+    }
+}
+
+/// Returns the paths of the library of the `interpreter`: its full path, then its file name to
+/// let the dynamic loader search for it.
+fn probe_interpreter(interpreter: &OsString) -> std::result::Result<Vec<PathBuf>, String> {
+    let output = Command::new(interpreter)
+        .args(["-c", SYSCONFIG_PROBE])
+        .output()
+        .map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        return Err(format!(
+            "{}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut lines = stdout.lines();
+    let lib_dir = lines.next().unwrap_or_default().trim();
+    let lib_name = lines.next().unwrap_or_default().trim();
+    if lib_name.is_empty() || lib_name.ends_with(".a") {
+        return Err("the interpreter has no Python shared library".to_string());
+    }
+
+    let mut candidates = Vec::with_capacity(2);
+    if !lib_dir.is_empty() {
+        candidates.push(PathBuf::from(lib_dir).join(lib_name));
+    }
+    candidates.push(PathBuf::from(lib_name));
+    Ok(candidates)
+}
+
+fn open(lib_name: OsString) -> std::result::Result<Library, libloading::Error> {
+    unsafe {
+        #[cfg(target_family = "unix")]
+        let lib = Library::open(Some(lib_name), LOAD_FLAGS);
+
+        #[cfg(target_family = "windows")]
+        let lib = Library::new(lib_name);
+
+        lib
+    }
+}
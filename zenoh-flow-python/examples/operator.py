@@ -34,10 +34,9 @@ class MyOperator(Operator):
         return None
 
     async def iteration(self) -> None:
-        # in order to wait on multiple input streams use:
-        # https://docs.python.org/3/library/asyncio-task.html#asyncio.gather
-        # or
-        # https://docs.python.org/3/library/asyncio-task.html#asyncio.wait
+        # in order to wait on multiple input streams, keep the `inputs`
+        # and use:
+        # port_id, data_msg = await self.inputs.select(["Data", "Other"])
         data_msg = await self.in_stream.recv()
 
         await self.output.send(data_msg.data)
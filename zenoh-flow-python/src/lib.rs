@@ -13,12 +13,19 @@
 //
 
 use pyo3::prelude::*;
-use zenoh_flow_python_commons::{RawInput, RawMessage, RawOutput};
+use zenoh_flow_python_commons::{
+    Codec, PortType, ProtobufCodec, RawInput, RawMessage, RawOutput, RawSelect, RecvTimeoutError,
+};
 
 #[pymodule]
-fn zenoh_flow(_py: Python, m: &PyModule) -> PyResult<()> {
+fn zenoh_flow(py: Python, m: &PyModule) -> PyResult<()> {
     m.add_class::<RawOutput>()?;
     m.add_class::<RawInput>()?;
     m.add_class::<RawMessage>()?;
+    m.add_class::<RawSelect>()?;
+    m.add_class::<Codec>()?;
+    m.add_class::<PortType>()?;
+    m.add_class::<ProtobufCodec>()?;
+    m.add("RecvTimeoutError", py.get_type::<RecvTimeoutError>())?;
     Ok(())
 }
@@ -156,10 +156,9 @@ Operator:
             return None
 
         async def iteration(self) -> None:
-            # in order to wait on multiple input streams use:
-            # https://docs.python.org/3/library/asyncio-task.html#asyncio.gather
-            # or
-            # https://docs.python.org/3/library/asyncio-task.html#asyncio.wait
+            # in order to wait on multiple input streams, keep the `inputs`
+            # and use:
+            # port_id, data_msg = await self.inputs.select(["Data", "Other"])
 
             data_msg = await self.in_stream.recv()
             new_data = data_msg.get_data() * 2
@@ -182,7 +181,29 @@ Operator:
 '''
 
 
-from .zenoh_flow import RawInput, RawOutput, RawMessage
+from .zenoh_flow import (
+    RawInput,
+    RawOutput,
+    RawMessage,
+    RawSelect,
+    RecvTimeoutError,
+    Codec,
+    PortType,
+    ProtobufCodec,
+)
 from zenoh_flow import types
-from .types import Inputs, Outputs, Input, Output, Message
+from .types import (
+    Inputs,
+    Outputs,
+    Input,
+    Output,
+    Message,
+    Timestamp,
+    NdArrayInput,
+    NdArrayOutput,
+    ArrowInput,
+    ArrowOutput,
+    register_codec,
+    get_codec,
+)
 from zenoh_flow import interfaces
@@ -14,7 +14,7 @@
 
 from zenoh_flow import Inputs, Outputs
 from zenoh_flow.types import Context
-from typing import Dict, Any
+from typing import Dict, Any, Optional
 
 
 class Operator(object):
@@ -37,6 +37,13 @@ class Operator(object):
 
     """
 
+    configuration_schema: Optional[Dict[str, Any]] = None
+    """
+    The JSON Schema of the configuration of the operator, validated before
+    `__init__` is called, `None` if the configuration is not validated.
+    See the *Configuration schema* section of the interfaces reference.
+    """
+
     def __init__(
         self,
         context: Context,
@@ -70,11 +77,48 @@ class Operator(object):
         The iteration method is called by the Zenoh Flow runtime, in a loop.
         This method is expected to gets data from the inputs,
         producing data and sends over the outputs
+
+        A node can instead define an `async def run(self)` coroutine, in
+        which case `iteration` is not called: `run` is called once each
+        time the node is started and is expected to run until the node is
+        stopped, which cancels it, e.g.
+        `async for msg in self.input: ...`.
         """
         raise NotImplementedError(
             "Please implement your own method, Operator is an interface"
         )
 
+    def on_start(self) -> None:
+        """
+        The on_start method is called by the Zenoh Flow runtime when it
+        starts the operator, including when it restarts it after stopping it,
+        before its first iteration.
+
+        It can also be defined as an `async def on_start(self)` coroutine,
+        run on the event loop of the operator for at most 5 seconds. An
+        exception it raises, or the expiry of the timeout, fails the start
+        of the operator.
+
+        Implementing this method is optional.
+        """
+        pass
+
+    def on_stop(self) -> None:
+        """
+        The on_stop method is called by the Zenoh Flow runtime when it
+        stops the operator, or when an iteration of the operator fails. The
+        operator can be started again afterwards, e.g. to pause a capture or
+        flush buffers without tearing down the state of the operator.
+
+        It can also be defined as an `async def on_stop(self)` coroutine,
+        run on the event loop of the operator for at most 5 seconds. An
+        exception it raises is logged. A `run` coroutine is cancelled
+        before `on_stop` is called.
+
+        Implementing this method is optional.
+        """
+        pass
+
     def finalize(self) -> None:
         """
         The finalize method is called by the zenoh flow runtime before
@@ -83,6 +127,12 @@ class Operator(object):
         It must implement all the required steps to destroy
         your operator state.
 
+        It can also be defined as an `async def finalize(self)` coroutine,
+        run on the event loop of the node, which is cancelled if it does
+        not complete within the `finalize-timeout` of the configuration, in
+        seconds, 5 by default. An exception raised by `finalize` is logged
+        with its traceback.
+
         """
         raise NotImplementedError(
             "Please implement your own method, Operator is an interface"
@@ -14,7 +14,7 @@
 
 from zenoh_flow import Inputs
 from zenoh_flow.types import Context
-from typing import Dict, Any
+from typing import Dict, Any, Optional
 
 
 class Sink(object):
@@ -34,6 +34,13 @@ class Sink(object):
     :type inputs: :class:`Inputs`
     """
 
+    configuration_schema: Optional[Dict[str, Any]] = None
+    """
+    The JSON Schema of the configuration of the sink, validated before
+    `__init__` is called, `None` if the configuration is not validated.
+    See the *Configuration schema* section of the interfaces reference.
+    """
+
     def __init__(
         self,
         context: Context,
@@ -64,17 +71,60 @@ class Sink(object):
         The run method is called by the Zenoh Flow runtime, in a loop.
         It allows wait on the inputs and interacting with the external world.
 
+        A node can instead define an `async def run(self)` coroutine, in
+        which case `iteration` is not called: `run` is called once each
+        time the node is started and is expected to run until the node is
+        stopped, which cancels it, e.g.
+        `async for msg in self.input: ...`.
+
         """
         raise NotImplementedError(
             "Please implement your own method, Sink is an interface"
         )
 
+    def on_start(self) -> None:
+        """
+        The on_start method is called by the Zenoh Flow runtime when it
+        starts the sink, including when it restarts it after stopping it,
+        before its first iteration.
+
+        It can also be defined as an `async def on_start(self)` coroutine,
+        run on the event loop of the sink for at most 5 seconds. An
+        exception it raises, or the expiry of the timeout, fails the start
+        of the sink.
+
+        Implementing this method is optional.
+        """
+        pass
+
+    def on_stop(self) -> None:
+        """
+        The on_stop method is called by the Zenoh Flow runtime when it
+        stops the sink, or when an iteration of the sink fails. The
+        sink can be started again afterwards, e.g. to pause a capture or
+        flush buffers without tearing down the state of the sink.
+
+        It can also be defined as an `async def on_stop(self)` coroutine,
+        run on the event loop of the sink for at most 5 seconds. An
+        exception it raises is logged. A `run` coroutine is cancelled
+        before `on_stop` is called.
+
+        Implementing this method is optional.
+        """
+        pass
+
     def finalize(self) -> None:
         """
         The finalize method is called by the zenoh flow runtime before
         destroying the node (e.g., upon stopping the data flow graph).
 
         It must implement all the required steps to destroy your sink state.
+
+        It can also be defined as an `async def finalize(self)` coroutine,
+        run on the event loop of the node, which is cancelled if it does
+        not complete within the `finalize-timeout` of the configuration, in
+        seconds, 5 by default. An exception raised by `finalize` is logged
+        with its traceback.
         """
         raise NotImplementedError(
             "Please implement your own method, Sink is an interface"
@@ -15,7 +15,7 @@
 
 from zenoh_flow import Outputs
 from zenoh_flow.types import Context
-from typing import Any, Dict
+from typing import Any, Dict, Optional
 
 
 class Source(object):
@@ -36,6 +36,13 @@ class Source(object):
 
     """
 
+    configuration_schema: Optional[Dict[str, Any]] = None
+    """
+    The JSON Schema of the configuration of the source, validated before
+    `__init__` is called, `None` if the configuration is not validated.
+    See the *Configuration schema* section of the interfaces reference.
+    """
+
     def __init__(
         self,
         context: Context,
@@ -66,17 +73,60 @@ class Source(object):
         The run method is called by the Zenoh Flow runtime, in a loop.
         It allows interact with the external world and
         send data over the outputs.
+
+        A node can instead define an `async def run(self)` coroutine, in
+        which case `iteration` is not called: `run` is called once each
+        time the node is started and is expected to run until the node is
+        stopped, which cancels it, e.g.
+        `while True: await self.output.send(await self.read_sensor())`.
         """
         raise NotImplementedError(
             "Please implement your own method, Source is an interface"
         )
 
+    def on_start(self) -> None:
+        """
+        The on_start method is called by the Zenoh Flow runtime when it
+        starts the source, including when it restarts it after stopping it,
+        before its first iteration.
+
+        It can also be defined as an `async def on_start(self)` coroutine,
+        run on the event loop of the source for at most 5 seconds. An
+        exception it raises, or the expiry of the timeout, fails the start
+        of the source.
+
+        Implementing this method is optional.
+        """
+        pass
+
+    def on_stop(self) -> None:
+        """
+        The on_stop method is called by the Zenoh Flow runtime when it
+        stops the source, or when an iteration of the source fails. The
+        source can be started again afterwards, e.g. to pause a capture or
+        flush buffers without tearing down the state of the source.
+
+        It can also be defined as an `async def on_stop(self)` coroutine,
+        run on the event loop of the source for at most 5 seconds. An
+        exception it raises is logged. A `run` coroutine is cancelled
+        before `on_stop` is called.
+
+        Implementing this method is optional.
+        """
+        pass
+
     def finalize(self) -> None:
         """
         The finalize method is called by the zenoh flow runtime
         before destroying the node (e.g., upon stopping the data flow graph).
 
         It must implement all the required steps to destroy your source state.
+
+        It can also be defined as an `async def finalize(self)` coroutine,
+        run on the event loop of the node, which is cancelled if it does
+        not complete within the `finalize-timeout` of the configuration, in
+        seconds, 5 by default. An exception raised by `finalize` is logged
+        with its traceback.
         """
         raise NotImplementedError(
             "Please implement your own method, Source is an interface"
@@ -13,13 +13,163 @@
 #
 
 
-from zenoh_flow import RawInput, RawOutput
-from typing import Callable, Any, TypeVar, Optional, Dict
+from zenoh_flow import RawInput, RawOutput, RawMessage, RawSelect, Codec, PortType
+from typing import Callable, Any, TypeVar, Optional, Dict, List, Tuple
+from datetime import datetime, timezone
+import functools
 
 
 T = TypeVar("T")
 
 
+__codecs: Dict[str, Tuple[Callable[[Any], Any], Callable[[memoryview], Any]]] = {
+    name: (codec.encode, codec.decode)
+    for name, codec in (
+        (name, Codec(name)) for name in ("json", "msgpack", "cbor", "pickle")
+    )
+}
+
+
+def register_codec(
+    name: str,
+    serializer: Callable[[Any], Any],
+    deserializer: Callable[[memoryview], Any],
+) -> None:
+    """
+    Registers the codec `name`, that can then be used to take inputs and
+    outputs, e.g. `outputs.take("Out", codec=name)`.
+
+    The codecs `json`, `msgpack`, `cbor` and `pickle` are built in Zenoh
+    Flow, registering a codec with the same name replaces it. The `json`
+    codec raises a `ValueError` when encoding `bytes`, `NaN` or `Infinity`,
+    which JSON cannot represent.
+
+    Warning: decoding with the `pickle` codec can run arbitrary code sent
+    by any upstream node, it should only be used when all the nodes of the
+    data flow, and the network, are trusted.
+
+    Args:
+        name (str): Name of the codec
+        serializer (Callable[[Any], Any]): Serialization function,
+            returning a bytes-like object.
+        deserializer (Callable[[memoryview], Any]): Deserialization function.
+    """
+    __codecs[name] = (serializer, deserializer)
+
+
+def get_codec(name: str) -> Tuple[Callable[[Any], Any], Callable[[memoryview], Any]]:
+    """
+    Returns the `(serializer, deserializer)` pair of the codec `name`.
+
+    Raises a `ValueError` if no codec is registered with this name. The
+    deserializer of the `pickle` codec can run arbitrary code, see
+    `register_codec`.
+    """
+    codec = __codecs.get(name, None)
+    if codec is None:
+        raise ValueError(
+            f"Unknown codec {name}, registered codecs: {', '.join(__codecs)}"
+        )
+    return codec
+
+
+def _with_codec(
+    function: Optional[Callable],
+    codec: Optional[str],
+    index: int,
+    port_type: Optional[PortType],
+) -> Tuple[Callable, Optional[str]]:
+    if function is not None:
+        if codec is not None:
+            raise ValueError("A (de)serializer and a codec cannot both be provided")
+        return (function, None)
+    declared = None if port_type is None else port_type.codec
+    if codec is None:
+        if declared is None:
+            raise ValueError("Either a (de)serializer or a codec must be provided")
+        codec = port_type.name
+    if declared is not None and codec == port_type.name:
+        return ((declared.encode, declared.decode)[index], codec)
+    return (get_codec(codec)[index], codec)
+
+
+# The Python type expected for the port types that have a counterpart in Python.
+_PYTHON_TYPES: Dict[str, type] = {
+    **{
+        name: int
+        for name in (
+            "u8",
+            "u16",
+            "u32",
+            "u64",
+            "u128",
+            "usize",
+            "i8",
+            "i16",
+            "i32",
+            "i64",
+            "i128",
+            "isize",
+            "int",
+        )
+    },
+    **{name: float for name in ("f32", "f64", "float")},
+    "bool": bool,
+    **{name: str for name in ("String", "string", "str")},
+    **{name: bytes for name in ("Vec<u8>", "bytes")},
+}
+
+
+def _check_port_type(
+    port_id: str,
+    port_type: Optional[PortType],
+    taken: str,
+    data_type: Any = None,
+    codec: Optional[str] = None,
+):
+    """
+    Raises a `TypeError` if the port `port_id` is taken, as `taken`
+    (`typed`, `ndarray` or `arrow`), with a type that disagrees with the
+    type declared for the port.
+
+    Declared types that are unknown to Zenoh Flow are not checked. The
+    integer types only accept subclasses of `int` other than `bool`.
+    """
+    if port_type is None:
+        return
+    name = port_type.name
+
+    if name in ("ndarray", "arrow"):
+        matches = taken == name
+    elif port_type.codec is not None or name in __codecs:
+        matches = taken == "typed" and codec == name
+    elif name in _PYTHON_TYPES:
+        expected = _PYTHON_TYPES[name]
+        matches = taken == "typed" and (
+            data_type is None
+            or (
+                isinstance(data_type, type)
+                and issubclass(data_type, expected)
+                and not (expected is int and issubclass(data_type, bool))
+            )
+        )
+    else:
+        matches = True
+
+    if not matches:
+        if taken != "typed":
+            how = f"with take_{taken}"
+        elif port_type.codec is not None or name in __codecs:
+            how = (
+                "with a custom (de)serializer"
+                if codec is None
+                else f"with the codec {codec}"
+            )
+        else:
+            how = f"as {getattr(data_type, '__name__', data_type)}"
+        raise TypeError(f"Port {port_id} is declared as {name} but is taken {how}")
+
+
 class Context(object):
     """
     A Zenoh Flow context.
@@ -53,19 +203,55 @@ class Context(object):
         )
 
 
+@functools.total_ordering
 class Timestamp(object):
     """
-    The Zenoh (Flow) timestamp.
+    The Zenoh (Flow) timestamp, as generated by a Hybrid Logical Clock.
+
+    Timestamps are totally ordered, first by time then by producer ID, as
+    in Zenoh, and hashable: they can be used to order and de-duplicate the
+    messages received from several runtimes.
 
     Attributes:
-        ntp     NTP Timestamp
-        id      UUID associated with the Timestamp producer.
+        ntp     NTP64 time: the 32 most significant bits are the seconds
+                since the UNIX epoch, the 32 least significant bits the
+                fraction of second.
+        id      ID of the HLC that produced the Timestamp: its bytes in
+                hexadecimal, least significant byte first.
     """
 
     def __init__(self, ntp: int, id: str):
         self.ntp = ntp
         self.id = id
 
+    def to_seconds(self) -> float:
+        """
+        Returns the number of seconds since the UNIX epoch.
+        """
+        return (self.ntp >> 32) + (self.ntp & 0xFFFFFFFF) / (1 << 32)
+
+    def to_datetime(self) -> datetime:
+        """
+        Returns the time as a timezone-aware (UTC) `datetime`.
+        """
+        return datetime.fromtimestamp(self.to_seconds(), tz=timezone.utc)
+
+    def __key(self):
+        return (self.ntp, int.from_bytes(bytes.fromhex(self.id), "little"))
+
+    def __eq__(self, other):
+        if not isinstance(other, Timestamp):
+            return NotImplemented
+        return self.__key() == other.__key()
+
+    def __lt__(self, other):
+        if not isinstance(other, Timestamp):
+            return NotImplemented
+        return self.__key() < other.__key()
+
+    def __hash__(self):
+        return hash(self.__key())
+
     def __repr__(self):
         return self.__str__()
 
@@ -81,7 +267,7 @@ class Message:
     `get_data` will return an empty list.
     """
 
-    def __init__(self, data: Any, ts: int, watermark: bool):
+    def __init__(self, data: Any, ts: Timestamp, watermark: bool):
         self.__data = data
         self.__ts = ts
         self.__watermark = watermark
@@ -93,7 +279,7 @@ class Message:
         """
         return self.__data
 
-    def get_ts(self) -> int:
+    def get_ts(self) -> Timestamp:
         """
         Returns the data timestamp.
         """
@@ -112,26 +298,78 @@ class Input:
     """
 
     def __init__(
-        self, inner: RawInput, input_type: T, deserializer: Callable[[bytes], T]
+        self,
+        inner: RawInput,
+        input_type: T,
+        deserializer: Callable[[memoryview], T],
     ):
         self.__deserializer = deserializer
         self.__inner = inner
         self.__type = input_type
 
-    async def recv(self) -> Message:
+    async def recv(self, timeout: Optional[float] = None) -> Message:
         """
         Returns the first `DataMessage` that was received, *asynchronously*,
         on any of the channels associated with this Input.
 
         If several `DataMessage` are received at the same time,
         one is randomly selected.
+
+        If a `timeout`, in seconds, is provided and no message is received
+        before it expires, a `RecvTimeoutError` is raised.
+
+        The deserializer is given a read-only `memoryview` over the
+        received bytes, no copy is performed.
         """
-        data_msg = await self.__inner.recv()
+        return self._to_message(await self.__inner.recv(timeout))
+
+    async def recv_batch(
+        self, max_items: int, max_wait: Optional[float] = None
+    ) -> List[Message]:
+        """
+        Returns, *asynchronously*, a list of at most `max_items`
+        `DataMessage` received on the channels associated with this Input.
+
+        The messages already queued are drained without waiting. If
+        `max_wait`, in seconds, is provided, messages are awaited until
+        `max_items` are received or `max_wait` expires — the list is empty
+        if no message was received in the meantime. Otherwise, only the
+        first message is awaited.
+        """
+        data_msgs = await self.__inner.recv_batch(max_items, max_wait)
+        return [self._to_message(data_msg) for data_msg in data_msgs]
+
+    def try_recv(self) -> Optional[Message]:
+        """
+        Returns the first `DataMessage` that was received on any of the
+        channels associated with this Input, or `None` if all the channels
+        are empty.
+
+        This method does not block.
+        """
+        data_msg = self.__inner.try_recv()
+        if data_msg is None:
+            return None
+        return self._to_message(data_msg)
+
+    def _to_message(self, data_msg: RawMessage) -> Message:
         data = None
         if len(data_msg.data) > 0:
             data = self.__deserializer(data_msg.data)
-        msg = Message(data, data_msg.ts, data_msg.is_watermark)
-        return msg
+        return Message(data, data_msg.ts, data_msg.is_watermark)
+
+    def _select_entry(self) -> Tuple[RawInput, str]:
+        return (self.__inner, "bytes")
+
+    def __aiter__(self):
+        return self
+
+    async def __anext__(self) -> Message:
+        """
+        Returns the next `DataMessage`, the iteration stops when all the
+        channels associated with this Input are disconnected.
+        """
+        return self._to_message(await self.__inner.__anext__())
 
     def port_id(self) -> str:
         """
@@ -146,7 +384,7 @@ class Output:
     """
 
     def __init__(
-        self, inner: RawOutput, output_type: T, serializer: Callable[[T], bytes]
+        self, inner: RawOutput, output_type: T, serializer: Callable[[T], Any]
     ):
         self.__serializer = serializer
         self.__inner = inner
@@ -159,6 +397,10 @@ class Output:
         If no timestamp is provided, the current timestamp
         — as per the HLC — is taken.
 
+        The serializer can return any object implementing the buffer
+        protocol (`bytes`, `bytearray`, `memoryview`, NumPy array...),
+        its content is not copied.
+
         If an error occurs while sending the message on a channel,
         we still try to send it on the remaining channels.
         For each failing channel, an error is logged and counted for.
@@ -166,6 +408,197 @@ class Output:
         ser_data = self.__serializer(data)
         return await self.__inner.send(ser_data, ts)
 
+    async def send_batch(
+        self, items: List[Tuple[T, Optional[int]]]
+    ) -> List[Optional[str]]:
+        """
+        Send, *asynchronously* and sequentially, the `(data, ts)` items on
+        all channels.
+
+        Returns a list with, for each item, `None` if it was sent or the
+        reason why it was not: a failing item, including one that cannot be
+        serialized, does not prevent the following ones from being sent.
+        """
+        results: List[Optional[str]] = [None] * len(items)
+        ser_items = []
+        positions = []
+        for position, (data, ts) in enumerate(items):
+            try:
+                ser_items.append((self.__serializer(data), ts))
+                positions.append(position)
+            except Exception as e:
+                results[position] = str(e)
+
+        sent = await self.__inner.send_batch(ser_items)
+        for position, result in zip(positions, sent):
+            results[position] = result
+        return results
+
+    async def send_watermark(self, ts: Optional[int] = None):
+        """
+        Send, *asynchronously*, a watermark on all channels.
+
+        A watermark signals to downstream nodes that no message with a
+        lower timestamp will be sent afterwards on this output, they
+        receive it as a `Message` whose `is_watermark` returns `True`.
+
+        If no timestamp is provided, the current timestamp
+        — as per the HLC — is taken.
+        """
+        return await self.__inner.send_watermark(ts)
+
+    def port_id(self) -> str:
+        """
+        Returns the ID associated with this `Output`.
+        """
+        return self.__inner.port_id()
+
+
+class NdArrayInput:
+    """
+    Channel that receives NumPy arrays from upstream nodes.
+
+    The arrays are decoded by Zenoh Flow: they are read-only and share
+    their memory with the received messages.
+    """
+
+    def __init__(self, inner: RawInput):
+        self.__inner = inner
+
+    async def recv(self, timeout: Optional[float] = None) -> Message:
+        """
+        Returns the first `DataMessage` that was received, *asynchronously*,
+        on any of the channels associated with this Input.
+
+        If a `timeout`, in seconds, is provided and no message is received
+        before it expires, a `RecvTimeoutError` is raised.
+
+        The data of the message is a read-only `numpy.ndarray`, or `None`
+        if the message is a `Watermark`.
+        """
+        return self._to_message(await self.__inner.recv_ndarray(timeout))
+
+    def _to_message(self, data_msg: RawMessage) -> Message:
+        data = None if data_msg.is_watermark else data_msg.data
+        return Message(data, data_msg.ts, data_msg.is_watermark)
+
+    def _select_entry(self) -> Tuple[RawInput, str]:
+        return (self.__inner, "ndarray")
+
+    def port_id(self) -> str:
+        """
+        Returns the ID associated with this `Input`.
+        """
+        return self.__inner.port_id()
+
+
+class NdArrayOutput:
+    """
+    Channel that sends NumPy arrays to downstream nodes.
+
+    The arrays are encoded by Zenoh Flow along with their dtype, shape and
+    strides.
+    """
+
+    def __init__(self, inner: RawOutput):
+        self.__inner = inner
+
+    async def send(self, data: Any, ts: Optional[int] = None):
+        """
+        Send, *asynchronously*, the array on all channels.
+
+        `data` can be a `numpy.ndarray` or any object NumPy can convert to
+        one. The array should not be modified after being sent.
+
+        If no timestamp is provided, the current timestamp
+        — as per the HLC — is taken.
+        """
+        return await self.__inner.send_ndarray(data, ts)
+
+    async def send_watermark(self, ts: Optional[int] = None):
+        """
+        Send, *asynchronously*, a watermark on all channels.
+
+        If no timestamp is provided, the current timestamp
+        — as per the HLC — is taken.
+        """
+        return await self.__inner.send_watermark(ts)
+
+    def port_id(self) -> str:
+        """
+        Returns the ID associated with this `Output`.
+        """
+        return self.__inner.port_id()
+
+
+class ArrowInput:
+    """
+    Channel that receives Apache Arrow record batches from upstream nodes.
+
+    Each message carries a single `pyarrow.RecordBatch`, exchanged in the
+    Arrow IPC streaming format: the batches can as well be sent by Rust
+    nodes.
+    """
+
+    def __init__(self, inner: RawInput):
+        self.__inner = inner
+
+    async def recv(self, timeout: Optional[float] = None) -> Message:
+        """
+        Returns the first `DataMessage` that was received, *asynchronously*,
+        on any of the channels associated with this Input.
+
+        If a `timeout`, in seconds, is provided and no message is received
+        before it expires, a `RecvTimeoutError` is raised.
+
+        The data of the message is a `pyarrow.RecordBatch`, or `None`
+        if the message is a `Watermark`.
+        """
+        return self._to_message(await self.__inner.recv_arrow(timeout))
+
+    def _to_message(self, data_msg: RawMessage) -> Message:
+        data = None if data_msg.is_watermark else data_msg.data
+        return Message(data, data_msg.ts, data_msg.is_watermark)
+
+    def _select_entry(self) -> Tuple[RawInput, str]:
+        return (self.__inner, "arrow")
+
+    def port_id(self) -> str:
+        """
+        Returns the ID associated with this `Input`.
+        """
+        return self.__inner.port_id()
+
+
+class ArrowOutput:
+    """
+    Channel that sends Apache Arrow record batches to downstream nodes.
+
+    The batches are written in the Arrow IPC streaming format, one batch
+    per message, only if they are sent to another process.
+    """
+
+    def __init__(self, inner: RawOutput):
+        self.__inner = inner
+
+    async def send(self, data: Any, ts: Optional[int] = None):
+        """
+        Send, *asynchronously*, the `pyarrow.RecordBatch` on all channels.
+
+        If no timestamp is provided, the current timestamp
+        — as per the HLC — is taken.
+        """
+        return await self.__inner.send_arrow(data, ts)
+
+    async def send_watermark(self, ts: Optional[int] = None):
+        """
+        Send, *asynchronously*, a watermark on all channels.
+
+        If no timestamp is provided, the current timestamp
+        — as per the HLC — is taken.
+        """
+        return await self.__inner.send_watermark(ts)
+
     def port_id(self) -> str:
         """
         Returns the ID associated with this `Output`.
@@ -177,34 +610,175 @@ class Inputs:
     """
     The `Inputs` structure contains all the receiving channels
     we created for a `Sink` or an `Operator`.
+
+    The node must take all of them in its constructor: the creation of the
+    node fails if one of them is never taken or if an unknown port is taken.
     """
 
-    def __init__(self, inputs: Dict[str, RawInput]):
+    def __init__(
+        self,
+        inputs: Dict[str, RawInput],
+        port_types: Optional[Dict[str, PortType]] = None,
+    ):
         self.__inputs = inputs
+        self.__port_types = {} if port_types is None else port_types
+        self.__claimed = set()
+        self.__unknown = set()
+        self.__taken = {}
+        self.__selects = {}
 
     def take(
-        self, port_id: str, input_type: T, deserializer: Callable[[bytes], T]
+        self,
+        port_id: str,
+        input_type: T = None,
+        deserializer: Optional[Callable[[memoryview], T]] = None,
+        codec: Optional[str] = None,
     ) -> Input:
         """
         Returns the typed `Input` associated to the provided `port_id`,
         if one is associated, otherwise `None` is returned.
 
+        Either a `deserializer` or the name of a registered `codec` must be
+        provided, see `register_codec`. They can be omitted if the port has
+        a Protobuf type, see `port_type`: the input then exchanges the
+        messages as dictionaries.
+
+        The `deserializer` is given a read-only `memoryview` over the
+        received bytes, and no longer `bytes`: a deserializer calling a
+        method of `bytes`, e.g. `data.decode()`, or a function that only
+        accepts `bytes`, e.g. `json.loads`, has to convert it first with
+        `bytes(data)`.
+
+        A `TypeError` is raised if the type declared for the port
+        disagrees with `input_type` or the codec.
+
         Args:
             port_id (str): Id associated with the input
             input_type (T): Type of data being received into this input
-            deserializer (Callable[[bytes], T]): Deserialization function
-                for the given type.
+            deserializer (Callable[[memoryview], T]): Deserialization
+                function for the given type.
+            codec (str): Name of the codec decoding the data
 
         Returns:
             Input: The typed associated input
 
         """
-        in_stream = self.__inputs.get(port_id, None)
+        port_type = self.__port_types.get(port_id, None)
+        deserializer, codec = _with_codec(deserializer, codec, 1, port_type)
+        _check_port_type(port_id, port_type, "typed", input_type, codec)
+        in_stream = self.__lookup(port_id)
         if in_stream is None:
             return None
         in_stream = Input(in_stream, input_type, deserializer)
+        return self.__track(port_id, in_stream)
+
+    def take_ndarray(self, port_id: str) -> NdArrayInput:
+        """
+        Returns the `NdArrayInput` associated to the provided `port_id`,
+        if one is associated, otherwise `None` is returned.
+
+        A NdArrayInput receives the NumPy arrays sent by a `NdArrayOutput`.
+
+        Args:
+            port_id (str): Id associated with the input
+
+        Returns:
+            NdArrayInput: The associated NumPy input
+        """
+        _check_port_type(port_id, self.__port_types.get(port_id, None), "ndarray")
+        in_stream = self.__lookup(port_id)
+        if in_stream is None:
+            return None
+        return self.__track(port_id, NdArrayInput(in_stream))
+
+    def take_arrow(self, port_id: str) -> ArrowInput:
+        """
+        Returns the `ArrowInput` associated to the provided `port_id`,
+        if one is associated, otherwise `None` is returned.
+
+        An ArrowInput receives the record batches sent by an `ArrowOutput`.
+
+        Args:
+            port_id (str): Id associated with the input
+
+        Returns:
+            ArrowInput: The associated Arrow input
+        """
+        _check_port_type(port_id, self.__port_types.get(port_id, None), "arrow")
+        in_stream = self.__lookup(port_id)
+        if in_stream is None:
+            return None
+        return self.__track(port_id, ArrowInput(in_stream))
+
+    async def select(self, port_ids: List[str]) -> Tuple[str, Message]:
+        """
+        Returns, *asynchronously*, the first `DataMessage` that was
+        received on any of the inputs associated to the provided `port_ids`,
+        along with the id of that input.
+
+        The selection is done by Zenoh Flow, which keeps receiving from
+        the inputs between two calls with the same `port_ids`: it should be
+        preferred to `asyncio.wait`, which creates and cancels a task per
+        input at every call. When messages are available on several inputs,
+        they are served in turn so that no input can starve the others.
+
+        Only the inputs taken by the node can be selected, which are all
+        its inputs once it is created, see `Inputs`. The data of the message
+        is decoded as per the input returned by `take`, `take_ndarray` or
+        `take_arrow`; the data of an input taken with `take_raw` is a
+        read-only `memoryview`. A `ValueError` is raised for an unknown
+        port.
+
+        Args:
+            port_ids (List[str]): Ids associated with the inputs
+
+        Returns:
+            Tuple[str, Message]: The id of the input and the message
+        """
+        key = tuple(port_ids)
+        raw_select = self.__selects.get(key, None)
+        if raw_select is None:
+            entries = []
+            for port_id in port_ids:
+                in_stream = self.__inputs.get(port_id, None)
+                if in_stream is None:
+                    raise ValueError(f"No input is associated to {port_id}")
+                typed = self.__taken.get(port_id, None)
+                entries.append(
+                    (in_stream, "bytes") if typed is None else typed._select_entry()
+                )
+            raw_select = RawSelect(entries)
+            self.__selects[key] = raw_select
+
+        port_id, data_msg = await raw_select.recv()
+        typed = self.__taken.get(port_id, None)
+        if typed is None:
+            data = None if data_msg.is_watermark else data_msg.data
+            return (port_id, Message(data, data_msg.ts, data_msg.is_watermark))
+        return (port_id, typed._to_message(data_msg))
+
+    def __track(self, port_id: str, in_stream: Any) -> Any:
+        self.__taken[port_id] = in_stream
+        self.__selects.clear()
         return in_stream
 
+    def port_type(self, port_id: str) -> Optional[PortType]:
+        """
+        Returns the type declared, under the `port-types` key of the
+        configuration, for the provided `port_id`, `None` if the node has no
+        such port.
+
+        A type `protobuf:<package.Message>` is resolved against the
+        descriptor set given by the `protobuf-descriptor-set` key.
+
+        Taking a port in a way that disagrees with its declared type raises
+        a `TypeError`: e.g. taking a port declared as `ndarray` with `take`,
+        or a port declared as `usize` with an `input_type` other than `int`
+        — `bool` included. The type of every port must be declared, the
+        type written in the descriptor is not given to the node.
+        """
+        return self.__port_types.get(port_id, None)
+
     def take_raw(self, port_id: str) -> RawInput:
         """
         Returns the RawInput associated to the provided `port_id`,
@@ -218,7 +792,27 @@ class Inputs:
         Returns:
             RawInput: The raw associated input
         """
-        return self.__inputs.get(port_id, None)
+        return self.__lookup(port_id)
+
+    def __lookup(self, port_id: str) -> Optional[RawInput]:
+        in_stream = self.__inputs.get(port_id, None)
+        if in_stream is None:
+            self.__unknown.add(port_id)
+        else:
+            self.__claimed.add(port_id)
+        return in_stream
+
+    def _untaken(self) -> List[str]:
+        """
+        Returns the ids of the inputs that were never taken.
+        """
+        return sorted(set(self.__inputs) - self.__claimed)
+
+    def _unknown(self) -> List[str]:
+        """
+        Returns the ids, unknown to the node, that were used to take inputs.
+        """
+        return sorted(self.__unknown)
 
     def __repr__(self):
         return self.__str__()
@@ -231,34 +825,115 @@ class Outputs:
     """
     The `Outputs` structure contains all the sender channels
     we created for a `Source` or an `Operator`.
+
+    The node must take all of them in its constructor: the creation of the
+    node fails if one of them is never taken or if an unknown port is taken.
     """
 
-    def __init__(self, outputs: Dict[str, RawOutput]):
+    def __init__(
+        self,
+        outputs: Dict[str, RawOutput],
+        port_types: Optional[Dict[str, PortType]] = None,
+    ):
         self.__outputs = outputs
+        self.__port_types = {} if port_types is None else port_types
+        self.__claimed = set()
+        self.__unknown = set()
 
     def take(
-        self, port_id: str, output_type: T, serializer: Callable[[T], bytes]
+        self,
+        port_id: str,
+        output_type: T = None,
+        serializer: Optional[Callable[[T], Any]] = None,
+        codec: Optional[str] = None,
     ) -> Output:
         """
 
         Returns the typed `Output` associated to the provided `port_id`,
         if one is associated, otherwise `None` is returned.
 
+        Either a `serializer` or the name of a registered `codec` must be
+        provided, see `register_codec`. They can be omitted if the port has
+        a Protobuf type, see `port_type`: the output then exchanges the
+        messages as dictionaries.
+
+        A `TypeError` is raised if the type declared for the port
+        disagrees with `output_type` or the codec.
+
         Args:
             port_id (str): Id associated with the output
             output_type (T): Type of data being sent to this output
-            serializer (Callable[[T], bytes]): Serialization function
-                for the given type.
+            serializer (Callable[[T], Any]): Serialization function
+                for the given type, returning a bytes-like object.
+            codec (str): Name of the codec encoding the data
 
         Returns:
             Output: The typed associated output
         """
-        out_stream = self.__outputs.get(port_id, None)
+        port_type = self.__port_types.get(port_id, None)
+        serializer, codec = _with_codec(serializer, codec, 0, port_type)
+        _check_port_type(port_id, port_type, "typed", output_type, codec)
+        out_stream = self.__lookup(port_id)
         if out_stream is None:
             return None
         out_stream = Output(out_stream, output_type, serializer)
         return out_stream
 
+    def take_ndarray(self, port_id: str) -> NdArrayOutput:
+        """
+        Returns the `NdArrayOutput` associated to the provided `port_id`,
+        if one is associated, otherwise `None` is returned.
+
+        A NdArrayOutput sends NumPy arrays, received with a `NdArrayInput`.
+
+        Args:
+            port_id (str): Id associated with the output
+
+        Returns:
+            NdArrayOutput: The associated NumPy output
+        """
+        _check_port_type(port_id, self.__port_types.get(port_id, None), "ndarray")
+        out_stream = self.__lookup(port_id)
+        if out_stream is None:
+            return None
+        return NdArrayOutput(out_stream)
+
+    def take_arrow(self, port_id: str) -> ArrowOutput:
+        """
+        Returns the `ArrowOutput` associated to the provided `port_id`,
+        if one is associated, otherwise `None` is returned.
+
+        An ArrowOutput sends record batches, received with an `ArrowInput`.
+
+        Args:
+            port_id (str): Id associated with the output
+
+        Returns:
+            ArrowOutput: The associated Arrow output
+        """
+        _check_port_type(port_id, self.__port_types.get(port_id, None), "arrow")
+        out_stream = self.__lookup(port_id)
+        if out_stream is None:
+            return None
+        return ArrowOutput(out_stream)
+
+    def port_type(self, port_id: str) -> Optional[PortType]:
+        """
+        Returns the type declared, under the `port-types` key of the
+        configuration, for the provided `port_id`, `None` if the node has no
+        such port.
+
+        A type `protobuf:<package.Message>` is resolved against the
+        descriptor set given by the `protobuf-descriptor-set` key.
+
+        Taking a port in a way that disagrees with its declared type raises
+        a `TypeError`: e.g. taking a port declared as `ndarray` with `take`,
+        or a port declared as `usize` with an `output_type` other than `int`
+        — `bool` included. The type of every port must be declared, the
+        type written in the descriptor is not given to the node.
+        """
+        return self.__port_types.get(port_id, None)
+
     def take_raw(self, port_id: str) -> RawOutput:
         """
         Returns the RawOutput associated to the provided `port_id`,
@@ -272,7 +947,27 @@ class Outputs:
         Returns:
             RawOutput: The raw associated output
         """
-        return self.__outputs.get(port_id, None)
+        return self.__lookup(port_id)
+
+    def __lookup(self, port_id: str) -> Optional[RawOutput]:
+        out_stream = self.__outputs.get(port_id, None)
+        if out_stream is None:
+            self.__unknown.add(port_id)
+        else:
+            self.__claimed.add(port_id)
+        return out_stream
+
+    def _untaken(self) -> List[str]:
+        """
+        Returns the ids of the outputs that were never taken.
+        """
+        return sorted(set(self.__outputs) - self.__claimed)
+
+    def _unknown(self) -> List[str]:
+        """
+        Returns the ids, unknown to the node, that were used to take outputs.
+        """
+        return sorted(self.__unknown)
 
     def __repr__(self):
         return self.__str__()